@@ -1,6 +1,7 @@
 //! Acala CLI library.
 
-use sc_cli::{KeySubcommand, RunCmd, SignCmd, VanityCmd, VerifyCmd};
+use sc_cli::{CliConfiguration, KeySubcommand, RunCmd, SharedParams, SignCmd, VanityCmd, VerifyCmd};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 /// Possible subcommands of the main binary.
@@ -53,6 +54,73 @@ pub enum Subcommand {
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
+
+	/// Export the genesis head data of the chain.
+	#[structopt(name = "export-genesis-head")]
+	ExportGenesisHead(ExportGenesisHeadCommand),
+
+	/// Export the genesis wasm of the chain.
+	#[structopt(name = "export-genesis-wasm")]
+	ExportGenesisWasm(ExportGenesisWasmCommand),
+
+	/// Print the fee and deposit parameters of the chain.
+	#[structopt(name = "chain-info")]
+	ChainInfo(ChainInfoCommand),
+}
+
+/// Command for exporting the SCALE encoded genesis head data of the chain.
+#[derive(Debug, StructOpt)]
+pub struct ExportGenesisHeadCommand {
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Write output in binary. Default is to write in hex. Only takes effect
+	/// when writing to an output file, stdout is always written in hex.
+	#[structopt(short, long)]
+	pub raw: bool,
+
+	/// The name of the chain for that the genesis head should be exported.
+	#[structopt(long)]
+	pub chain: Option<String>,
+
+	/// Print the byte length and the blake2-256 hash of the genesis head to
+	/// stderr instead of exporting it.
+	#[structopt(long)]
+	pub check: bool,
+}
+
+/// Command for exporting the genesis wasm code of the chain.
+#[derive(Debug, StructOpt)]
+pub struct ExportGenesisWasmCommand {
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Write output in binary. Default is to write in hex. Only takes effect
+	/// when writing to an output file, stdout is always written in hex.
+	#[structopt(short, long)]
+	pub raw: bool,
+
+	/// The name of the chain for that the genesis wasm should be exported.
+	#[structopt(long)]
+	pub chain: Option<String>,
+}
+
+/// Command for printing the fee and deposit parameters of the chain as JSON.
+/// The parameters are read from the state of the best block in the local
+/// database.
+#[derive(Debug, StructOpt)]
+pub struct ChainInfoCommand {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl CliConfiguration for ChainInfoCommand {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
 }
 
 /// An overarching CLI command definition.
@@ -2,8 +2,20 @@
 #![allow(clippy::borrowed_box)]
 
 use crate::cli::{Cli, Subcommand};
+use acala_primitives::{Balance, Block, CurrencyId};
+use codec::{Decode, Encode};
 use sc_cli::{Role, RuntimeVersion, SubstrateCli};
+use sc_client_api::StorageProvider;
+use serde::Serialize;
 use service::{chain_spec, IdentifyVariant};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hexdisplay::HexDisplay, storage::StorageKey};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Get, Hash as HashT, Header as HeaderT, Zero},
+	FixedU128,
+};
+use std::{io::Write, path::PathBuf};
 
 fn get_exec_name() -> Option<String> {
 	std::env::current_exe()
@@ -104,6 +116,133 @@ fn set_default_ss58_version(spec: &Box<dyn service::ChainSpec>) {
 	sp_core::crypto::set_default_ss58_version(ss58_version);
 }
 
+/// Generate the genesis block of the given chain spec, with the state root and
+/// extrinsics root calculated from the genesis storage.
+fn generate_genesis_block<Block: BlockT>(chain_spec: &Box<dyn sc_service::ChainSpec>) -> Result<Block, String> {
+	let storage = chain_spec.build_storage()?;
+
+	let child_roots = storage.children_default.iter().map(|(sk, child_content)| {
+		let state_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(
+			child_content.data.clone().into_iter().collect(),
+		);
+		(sk.clone(), state_root.encode())
+	});
+	let state_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(
+		storage.top.clone().into_iter().chain(child_roots).collect(),
+	);
+
+	let extrinsics_root = <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::trie_root(Vec::new());
+
+	Ok(Block::new(
+		<<Block as BlockT>::Header as HeaderT>::new(
+			Zero::zero(),
+			extrinsics_root,
+			state_root,
+			Default::default(),
+			Default::default(),
+		),
+		Default::default(),
+	))
+}
+
+/// Extract the genesis wasm code from the genesis storage of the given chain
+/// spec.
+fn extract_genesis_wasm(chain_spec: &Box<dyn sc_service::ChainSpec>) -> Result<Vec<u8>, String> {
+	let mut storage = chain_spec.build_storage()?;
+
+	storage
+		.top
+		.remove(sp_core::storage::well_known_keys::CODE)
+		.ok_or_else(|| "Could not find wasm file in genesis state!".into())
+}
+
+/// Write `data` to `output` file, or stdout if unspecified. Data is written
+/// in `0x` prefixed hex unless `raw` is set and writing to a file, so that
+/// binary data never corrupts the terminal.
+fn write_output(output: Option<&PathBuf>, raw: bool, data: Vec<u8>) -> sc_cli::Result<()> {
+	match output {
+		Some(output) => {
+			let output_buf = if raw {
+				data
+			} else {
+				format!("0x{:?}", HexDisplay::from(&data)).into_bytes()
+			};
+			std::fs::write(output, output_buf)?;
+		}
+		None => {
+			std::io::stdout().write_all(format!("0x{:?}", HexDisplay::from(&data)).as_bytes())?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Decode the `ChargeTransactionPayment` signed extension of an encoded
+/// extrinsic of the given runtime, and format its tip details.
+macro_rules! decode_payment_of {
+	($runtime:ident) => {
+		|encoded: &[u8]| -> Option<String> {
+			let extrinsic = service::$runtime::UncheckedExtrinsic::decode(&mut &*encoded).ok()?;
+			let (_, _, extra) = extrinsic.signature?;
+			let payment = extra.6;
+			Some(format!(
+				"tip: {:?}, fee payer: {:?}, non-native tip: {:?}, max fee: {:?}, priority multiplier: {:?}",
+				payment.tip(),
+				payment.requested_fee_payer(),
+				payment.non_native_tip(),
+				payment.max_fee(),
+				payment.priority_multiplier(),
+			))
+		}
+	};
+}
+
+/// The fee and deposit parameters of the accounts module.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainInfo {
+	best_block: <Block as BlockT>::Hash,
+	new_account_deposit: Balance,
+	max_slippage_swap_with_dex: FixedU128,
+	all_non_native_currency_ids: Vec<CurrencyId>,
+}
+
+/// Read the fee and deposit parameters of the given native runtime. The
+/// deposit and the slippage are read from the state at `at`, and fall back to
+/// the runtime defaults if not set in the state yet, the same as the module
+/// does.
+macro_rules! chain_info_of {
+	($runtime:ident, $client:expr, $at:expr) => {
+		ChainInfo {
+			best_block: $at,
+			new_account_deposit: read_accounts_storage($client, $at, b"NewAccountDepositAmount")?
+				.unwrap_or_else(service::$runtime::NewAccountDeposit::get),
+			max_slippage_swap_with_dex: read_accounts_storage($client, $at, b"MaxSlippage")?
+				.unwrap_or_else(service::$runtime::MaxSlippageSwapWithDEX::get),
+			all_non_native_currency_ids: service::$runtime::AllNonNativeCurrencyIds::get(),
+		}
+	};
+}
+
+/// Read and decode the value of the accounts module storage `name` at block
+/// `at`.
+fn read_accounts_storage<T: Decode>(
+	client: &service::Client,
+	at: <Block as BlockT>::Hash,
+	name: &[u8],
+) -> sc_cli::Result<Option<T>> {
+	let key = [sp_core::twox_128(b"Accounts"), sp_core::twox_128(name)].concat();
+	let data = client
+		.storage(&BlockId::Hash(at), &StorageKey(key))
+		.map_err(|e| format!("{}", e))?;
+
+	data.map(|data| {
+		T::decode(&mut &data.0[..]).map_err(|e| format!("Could not decode {}: {}", String::from_utf8_lossy(name), e))
+	})
+	.transpose()
+	.map_err(Into::into)
+}
+
 /// Parses acala specific CLI arguments and run the service.
 pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
@@ -127,9 +266,17 @@ pub fn run() -> sc_cli::Result<()> {
 
 			set_default_ss58_version(chain_spec);
 
+			let printer = if chain_spec.is_mandala() {
+				inspect::PaymentPrinter::new(decode_payment_of!(mandala_runtime))
+			} else if chain_spec.is_karura() {
+				inspect::PaymentPrinter::new(decode_payment_of!(karura_runtime))
+			} else {
+				inspect::PaymentPrinter::new(decode_payment_of!(acala_runtime))
+			};
+
 			runner.sync_run(|config| {
 				let (client, _, _) = service::build_full(config, false)?;
-				cmd.run(client)
+				cmd.run_with_printer(client, printer)
 			})
 		}
 
@@ -235,5 +382,53 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		}
+
+		Some(Subcommand::ExportGenesisHead(params)) => {
+			let chain_spec = cli.load_spec(&params.chain.clone().unwrap_or_default())?;
+			let block: Block = generate_genesis_block(&chain_spec)?;
+			let raw_header = block.header().encode();
+
+			if params.check {
+				eprintln!(
+					"length: {}, hash: 0x{:?}",
+					raw_header.len(),
+					HexDisplay::from(&sp_core::blake2_256(&raw_header))
+				);
+				return Ok(());
+			}
+
+			write_output(params.output.as_ref(), params.raw, raw_header)
+		}
+
+		Some(Subcommand::ExportGenesisWasm(params)) => {
+			let chain_spec = cli.load_spec(&params.chain.clone().unwrap_or_default())?;
+			let raw_wasm = extract_genesis_wasm(&chain_spec)?;
+
+			write_output(params.output.as_ref(), params.raw, raw_wasm)
+		}
+
+		Some(Subcommand::ChainInfo(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			let chain_spec = &runner.config().chain_spec;
+
+			set_default_ss58_version(chain_spec);
+
+			runner.sync_run(|mut config| {
+				let (client, _, _, _task_manager) = service::new_chain_ops(&mut config)?;
+				let at = client.info().best_hash;
+				let info = if config.chain_spec.is_mandala() {
+					chain_info_of!(mandala_runtime, &client, at)
+				} else if config.chain_spec.is_karura() {
+					chain_info_of!(karura_runtime, &client, at)
+				} else {
+					chain_info_of!(acala_runtime, &client, at)
+				};
+
+				let json = serde_json::to_string_pretty(&info).map_err(|e| format!("{}", e))?;
+				println!("{}", json);
+
+				Ok(())
+			})
+		}
 	}
 }
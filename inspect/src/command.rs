@@ -1,7 +1,7 @@
 //! Command ran by the CLI
 
 use crate::cli::{InspectCmd, InspectSubCmd};
-use crate::Inspector;
+use crate::{DebugPrinter, Inspector, PrettyPrinter};
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
@@ -16,10 +16,21 @@ impl InspectCmd {
 		B: Block,
 		B::Hash: FromStr,
 		CL: BlockBackend<B> + HeaderBackend<B> + 'static,
+	{
+		self.run_with_printer::<B, CL, DebugPrinter>(client, Default::default())
+	}
+
+	/// Run the inspect command, passing the inspector with the given printer.
+	pub fn run_with_printer<B, CL, P>(&self, client: Arc<CL>, printer: P) -> Result<()>
+	where
+		B: Block,
+		B::Hash: FromStr,
+		CL: BlockBackend<B> + HeaderBackend<B> + 'static,
+		P: PrettyPrinter<B>,
 	{
 		match Arc::try_unwrap(client) {
 			Ok(cli) => {
-				let inspect = Inspector::<B>::new(cli);
+				let inspect = Inspector::<B, P>::with_printer(cli, printer);
 
 				match &self.command {
 					InspectSubCmd::Block { input } => {
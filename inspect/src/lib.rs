@@ -45,6 +45,42 @@ impl<TBlock: Block> PrettyPrinter<TBlock> for DebugPrinter {
 	}
 }
 
+/// Debug printer which additionally prints the transaction payment details of
+/// signed extrinsics, as decoded by the runtime specific `decode_payment`.
+pub struct PaymentPrinter {
+	decode_payment: fn(&[u8]) -> Option<String>,
+}
+
+impl PaymentPrinter {
+	/// Create new payment printer with the `decode_payment` function, which
+	/// returns `None` if the encoded extrinsic is unsigned.
+	pub fn new(decode_payment: fn(&[u8]) -> Option<String>) -> Self {
+		PaymentPrinter { decode_payment }
+	}
+}
+
+impl<TBlock: Block> PrettyPrinter<TBlock> for PaymentPrinter {
+	fn fmt_block(&self, fmt: &mut fmt::Formatter, block: &TBlock) -> fmt::Result {
+		writeln!(fmt, "Header:")?;
+		writeln!(fmt, "{:?}", block.header())?;
+		writeln!(fmt, "Block bytes: {:?}", HexDisplay::from(&block.encode()))?;
+		writeln!(fmt, "Extrinsics ({})", block.extrinsics().len())?;
+		for (idx, ex) in block.extrinsics().iter().enumerate() {
+			writeln!(fmt, "- {}:", idx)?;
+			<PaymentPrinter as PrettyPrinter<TBlock>>::fmt_extrinsic(self, fmt, ex)?;
+		}
+		Ok(())
+	}
+
+	fn fmt_extrinsic(&self, fmt: &mut fmt::Formatter, extrinsic: &TBlock::Extrinsic) -> fmt::Result {
+		<DebugPrinter as PrettyPrinter<TBlock>>::fmt_extrinsic(&DebugPrinter, fmt, extrinsic)?;
+		if let Some(payment) = (self.decode_payment)(&extrinsic.encode()) {
+			writeln!(fmt, " Payment: {}", payment)?;
+		}
+		Ok(())
+	}
+}
+
 /// Aggregated error for `Inspector` operations.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, derive_more::From, derive_more::Display)]
@@ -0,0 +1,48 @@
+//! Runtime API definition for accounts module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The `too_many_arguments` warning originates from `decl_runtime_apis` macro.
+#![allow(clippy::too_many_arguments)]
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+	pub trait AccountsApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		fn recently_killed() -> Vec<(AccountId, BlockNumber)>;
+
+		fn usable_native_balance(who: AccountId) -> Balance;
+
+		fn treasury_account() -> AccountId;
+
+		fn total_swept_dust() -> Balance;
+	}
+
+	pub trait AccountsFeeApi<AccountId, Balance, Call, CurrencyId> where
+		AccountId: Codec,
+		Balance: Codec,
+		Call: Codec,
+		CurrencyId: Codec,
+	{
+		fn query_fee_in_currency(call: Call, len: u32, currency_id: CurrencyId) -> Option<Balance>;
+
+		fn fees_collected(currency_id: CurrencyId) -> Balance;
+
+		fn payable_fee_currencies(who: AccountId, fee: Balance) -> Vec<CurrencyId>;
+	}
+
+	pub trait AccountsBalanceApi<AccountId, Balance, CurrencyId> where
+		AccountId: Codec,
+		Balance: Codec,
+		CurrencyId: Codec,
+	{
+		fn all_balances(who: AccountId, include_zero: bool) -> Vec<(CurrencyId, Balance, Balance)>;
+
+		fn would_reap(who: AccountId, currency_id: CurrencyId, amount: Balance) -> bool;
+	}
+}
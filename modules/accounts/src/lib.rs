@@ -9,31 +9,32 @@
 
 use codec::{Decode, Encode};
 use frame_support::{
-	decl_error, decl_module, decl_storage,
+	debug, decl_error, decl_event, decl_module, decl_storage,
 	dispatch::{DispatchResult, Dispatchable},
 	ensure,
 	traits::{
-		Currency, ExistenceRequirement, Get, Happened, Imbalance, OnKilledAccount, OnUnbalanced, StoredMap,
-		WithdrawReason,
+		Currency, EnsureOrigin, ExistenceRequirement, FindAuthor, Get, Happened, Imbalance, LockIdentifier,
+		LockableCurrency, OnKilledAccount, OnUnbalanced, StoredMap, WithdrawReason, WithdrawReasons,
 	},
-	weights::{DispatchInfo, PostDispatchInfo, Weight},
-	IsSubType,
+	weights::{DispatchClass, DispatchInfo, PostDispatchInfo, Weight, WeightToFeePolynomial},
+	IsSubType, Parameter,
 };
 use frame_system::{self as system, ensure_signed, AccountInfo};
 use orml_traits::{MultiCurrency, MultiLockableCurrency, MultiReservableCurrency, OnReceived};
 use orml_utilities::with_transaction_result;
-use primitives::{Balance, CurrencyId};
+use primitives::{Balance, CurrencyId, EraIndex};
 use sp_runtime::{
 	traits::{
-		AccountIdConversion, CheckedSub, DispatchInfoOf, PostDispatchInfoOf, SaturatedConversion, Saturating,
-		SignedExtension, UniqueSaturatedInto, Zero,
+		AccountIdConversion, Bounded, CheckedSub, DispatchInfoOf, IdentifyAccount, One, PostDispatchInfoOf,
+		SaturatedConversion, Saturating, SignedExtension, UniqueSaturatedInto, Verify, Zero,
 	},
 	transaction_validity::{
 		InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError, ValidTransaction,
 	},
-	FixedPointOperand, ModuleId,
+	DispatchError, FixedPointNumber, FixedPointOperand, ModuleId, Perbill, RuntimeDebug,
 };
 use sp_std::convert::Infallible;
+use sp_std::marker::PhantomData;
 use sp_std::{prelude::*, vec};
 use support::{DEXManager, Ratio};
 
@@ -41,8 +42,305 @@ mod default_weight;
 mod mock;
 mod tests;
 
+/// The custom `InvalidTransaction` error code when the fee has been swapped
+/// into native currency, but withdrawing it is still blocked, e.g. by locks.
+pub const FEE_WITHDRAW_BLOCKED: u8 = 1;
+
+/// The custom `InvalidTransaction` error code when the native currency is not
+/// enough to pay the fee, and no non-native currency can be swapped for it.
+pub const FEE_SWAP_FAILED: u8 = 2;
+
+/// The domain tag of the payload signed for `set_fee_currency_signed`.
+pub const FEE_CURRENCY_PAYLOAD_TAG: &[u8] = b"acala/accounts/set_fee_currency";
+
+/// The target of the logs of this module.
+#[cfg(feature = "std")]
+const LOG_TARGET: &str = "runtime::accounts";
+
+/// The max exponent of the retry interval of a pending close request, which
+/// is `2^attempts` blocks.
+const MAX_CLOSE_RETRY_EXPONENT: u32 = 12;
+
 pub trait WeightInfo {
 	fn close_account(c: u32) -> Weight;
+	fn set_consolidation_owner() -> Weight;
+	fn request_close() -> Weight;
+	fn set_fee_currency_signed() -> Weight;
+	fn close_account_to_currency(c: u32) -> Weight;
+	fn set_fee_currency() -> Weight;
+	fn swap_fee() -> Weight;
+	fn cache_fee_path() -> Weight;
+	fn approve_fee_payer() -> Weight;
+	fn set_multisig_fee_payer() -> Weight;
+	fn set_auto_swap() -> Weight;
+	fn set_fee_swap_path() -> Weight;
+	fn try_open_account() -> Weight;
+	fn set_new_account_deposit() -> Weight;
+	fn set_max_slippage() -> Weight;
+	fn set_currency_slippage() -> Weight;
+	fn set_fee_free_call() -> Weight;
+	fn set_fee_relief_call() -> Weight;
+	fn set_allow_death_call() -> Weight;
+	fn set_call_fee_multiplier() -> Weight;
+	fn sweep_currencies(c: u32) -> Weight;
+	fn withdraw_fee(c: u32) -> Weight;
+	fn usable_native_balance() -> Weight;
+	fn reset_fees_collected(c: u32) -> Weight;
+}
+
+/// The verbosity level of events deposited by this module.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, RuntimeDebug)]
+pub enum EventLevel {
+	/// No event.
+	Off,
+	/// Only failure events.
+	Errors,
+	/// Failure and lifecycle events.
+	Normal,
+	/// All events, including the detail of every swap.
+	Verbose,
+}
+
+/// The reason why an account is killed.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum KillReason {
+	/// The account is closed, by itself or by governance.
+	Closed,
+	/// The native dust of the account which can not be opened is swept to treasury.
+	DustSwept,
+	/// The account is reaped when all its balances are gone.
+	Reaped,
+}
+
+/// Discount of the open account deposit for a specific account.
+pub trait OpenDepositDiscount<AccountId> {
+	/// The portion of `NewAccountDeposit` waived for `who`.
+	fn discount(who: &AccountId) -> Perbill;
+}
+
+impl<AccountId> OpenDepositDiscount<AccountId> for () {
+	fn discount(_who: &AccountId) -> Perbill {
+		Perbill::zero()
+	}
+}
+
+/// Policy of transferring currencies when closing account.
+pub trait TransferGuard<AccountId> {
+	/// Whether `currency_id` is allowed to be transferred from `from` to `to`.
+	fn allow(currency_id: CurrencyId, from: &AccountId, to: &AccountId) -> bool;
+}
+
+impl<AccountId> TransferGuard<AccountId> for () {
+	fn allow(_currency_id: CurrencyId, _from: &AccountId, _to: &AccountId) -> bool {
+		true
+	}
+}
+
+/// The references of account registered by other modules, besides the
+/// `refcount` of frame_system.
+pub trait AccountReferences<AccountId> {
+	/// Whether `who` has consumers which rely on the account to exist.
+	fn has_consumers(who: &AccountId) -> bool;
+	/// Whether `who` has providers which keep the account alive.
+	fn has_providers(who: &AccountId) -> bool;
+}
+
+impl<AccountId> AccountReferences<AccountId> for () {
+	fn has_consumers(_who: &AccountId) -> bool {
+		false
+	}
+
+	fn has_providers(_who: &AccountId) -> bool {
+		false
+	}
+}
+
+/// The decision of charging fee for a call.
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum FeeDecision<Balance> {
+	/// Charge the amount as fee.
+	Charge(Balance),
+	/// The call is free.
+	Free,
+	/// Reject the call.
+	Reject,
+}
+
+/// Call-aware policy of charging fee.
+pub trait CallFeePolicy<Call, Balance> {
+	/// Decide how to charge fee for `call`, which should pay `fee` by default.
+	fn decide(call: &Call, fee: Balance) -> FeeDecision<Balance>;
+}
+
+impl<Call, Balance> CallFeePolicy<Call, Balance> for () {
+	fn decide(_call: &Call, fee: Balance) -> FeeDecision<Balance> {
+		FeeDecision::Charge(fee)
+	}
+}
+
+/// A request to close the account once it can be reaped.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct CloseRequest<AccountId, BlockNumber> {
+	/// The recipient of the remaining currencies, None means the treasury.
+	pub recipient: Option<AccountId>,
+	/// The request is dropped after this block.
+	pub expire_at: BlockNumber,
+	/// The close is not retried before this block.
+	pub retry_at: BlockNumber,
+	/// The number of failed attempts, the retry interval doubles on each.
+	pub attempts: u32,
+}
+
+/// Resolver of the multisig account approved by a `pallet_multisig` call.
+pub trait MultisigApproval<AccountId, Call> {
+	/// The multisig account which `who` approves for by `call`, None if
+	/// `call` is not a multisig approval.
+	fn multisig_of(who: &AccountId, call: &Call) -> Option<AccountId>;
+}
+
+impl<AccountId, Call> MultisigApproval<AccountId, Call> for () {
+	fn multisig_of(_who: &AccountId, _call: &Call) -> Option<AccountId> {
+		None
+	}
+}
+
+/// The locked native balance which may still be used to pay transaction fee.
+///
+/// Security: the released balance is no longer locked, the lock owners(e.g.
+/// vesting) must tolerate their locks being reduced by transaction fees. As
+/// every transaction may release its fee from the locks, the whole locked
+/// balance can be drained by transactions over time, only the locks whose
+/// funds belong to the account itself should be allowed.
+pub trait FeeLockAllowance<AccountId> {
+	/// The locked native balance of `who` which can be released to pay fee.
+	fn allowance(who: &AccountId) -> Balance;
+	/// Release `amount` of the locked native balance of `who` to pay fee,
+	/// `amount` must not exceed the allowance.
+	fn release(who: &AccountId, amount: Balance);
+}
+
+impl<AccountId> FeeLockAllowance<AccountId> for () {
+	fn allowance(_who: &AccountId) -> Balance {
+		Zero::zero()
+	}
+
+	fn release(_who: &AccountId, _amount: Balance) {}
+}
+
+/// `FeeLockAllowance` of the `pallet_balances` locks, the locks in
+/// `LockLimits` can be reduced by at most their limits for a single fee.
+pub struct BalancesFeeLockAllowance<T, LockLimits>(PhantomData<(T, LockLimits)>);
+
+impl<T, LockLimits> BalancesFeeLockAllowance<T, LockLimits>
+where
+	T: pallet_balances::Trait<Balance = Balance>,
+	LockLimits: Get<Vec<(LockIdentifier, Balance)>>,
+{
+	/// The locks of `who` which block transaction payment, with their limits
+	/// if allowed.
+	fn fee_locks(who: &T::AccountId) -> Vec<(pallet_balances::BalanceLock<Balance>, Option<Balance>)> {
+		let lock_limits = LockLimits::get();
+		pallet_balances::Module::<T>::locks(who)
+			.into_iter()
+			.filter(|lock| lock.reasons != pallet_balances::Reasons::Misc)
+			.map(|lock| {
+				let limit = lock_limits
+					.iter()
+					.find(|(id, _)| *id == lock.id)
+					.map(|(_, limit)| *limit);
+				(lock, limit)
+			})
+			.collect()
+	}
+}
+
+impl<T, LockLimits> FeeLockAllowance<T::AccountId> for BalancesFeeLockAllowance<T, LockLimits>
+where
+	T: pallet_balances::Trait<Balance = Balance>,
+	LockLimits: Get<Vec<(LockIdentifier, Balance)>>,
+{
+	fn allowance(who: &T::AccountId) -> Balance {
+		// the fee is frozen by the max of the locks, which can only be reduced
+		// to the max of the not allowed locks and the allowed locks minus limits
+		let (mut frozen, mut floor) = (Balance::zero(), Balance::zero());
+		for (lock, limit) in Self::fee_locks(who) {
+			frozen = frozen.max(lock.amount);
+			floor = floor.max(limit.map_or(lock.amount, |limit| lock.amount.saturating_sub(limit)));
+		}
+		frozen.saturating_sub(floor)
+	}
+
+	fn release(who: &T::AccountId, amount: Balance) {
+		for (lock, limit) in Self::fee_locks(who) {
+			if let Some(limit) = limit {
+				let remaining = lock.amount.saturating_sub(amount.min(limit));
+				if remaining.is_zero() {
+					pallet_balances::Module::<T>::remove_lock(lock.id, who);
+				} else {
+					// keep the original reasons of the lock
+					let reasons = match lock.reasons {
+						pallet_balances::Reasons::Fee => WithdrawReason::TransactionPayment.into(),
+						pallet_balances::Reasons::Misc => WithdrawReasons::except(WithdrawReason::TransactionPayment),
+						pallet_balances::Reasons::All => WithdrawReasons::all(),
+					};
+					pallet_balances::Module::<T>::set_lock(lock.id, who, remaining, reasons);
+				}
+			}
+		}
+	}
+}
+
+/// Strategy to order the non-native currencies tried to pay fee.
+pub trait FeeOrderStrategy<T: Trait> {
+	/// Order `currency_ids` to be tried to pay fee for `who`.
+	fn order(who: &T::AccountId, currency_ids: Vec<CurrencyId>) -> Vec<CurrencyId>;
+}
+
+/// Try the currencies in the order of `AllNonNativeCurrencyIds`.
+pub struct FixedOrder;
+impl<T: Trait> FeeOrderStrategy<T> for FixedOrder {
+	fn order(_who: &T::AccountId, currency_ids: Vec<CurrencyId>) -> Vec<CurrencyId> {
+		currency_ids
+	}
+}
+
+/// Try the currency of which the account holds the most free balance first.
+pub struct ByUserBalance;
+impl<T: Trait> FeeOrderStrategy<T> for ByUserBalance {
+	fn order(who: &T::AccountId, mut currency_ids: Vec<CurrencyId>) -> Vec<CurrencyId> {
+		// read the balance of each currency once
+		currency_ids.sort_by_cached_key(|currency_id| {
+			sp_std::cmp::Reverse(<T as Trait>::Currency::free_balance(*currency_id, who))
+		});
+		currency_ids
+	}
+}
+
+/// Try the currency with the deepest DEX liquidity first. The liquidity is
+/// measured by the pool of the first hop of the fee swap path of currency,
+/// on the side of stable currency if the pool is against it.
+pub struct ByDexLiquidity;
+impl<T: Trait> FeeOrderStrategy<T> for ByDexLiquidity {
+	fn order(_who: &T::AccountId, mut currency_ids: Vec<CurrencyId>) -> Vec<CurrencyId> {
+		let stable_currency_id = T::StableCurrencyId::get();
+		// read the pool of each currency once
+		currency_ids.sort_by_cached_key(|currency_id| {
+			let depth = match Module::<T>::fee_swap_path(*currency_id).as_slice() {
+				[supply_currency_id, target_currency_id, ..] => {
+					let (supply_pool, target_pool) =
+						T::DEX::get_liquidity_pool(*supply_currency_id, *target_currency_id);
+					if *supply_currency_id == stable_currency_id {
+						supply_pool
+					} else {
+						target_pool
+					}
+				}
+				_ => Zero::zero(),
+			};
+			sp_std::cmp::Reverse(depth)
+		});
+		currency_ids
+	}
 }
 
 type PalletBalanceOf<T> =
@@ -52,6 +350,8 @@ type NegativeImbalanceOf<T> = <<T as pallet_transaction_payment::Trait>::Currenc
 >>::NegativeImbalance;
 
 pub trait Trait: system::Trait + pallet_transaction_payment::Trait + orml_currencies::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
 	/// All non-native currency ids in Acala.
 	type AllNonNativeCurrencyIds: Get<Vec<CurrencyId>>;
 
@@ -62,6 +362,10 @@ pub trait Trait: system::Trait + pallet_transaction_payment::Trait + orml_curren
 	/// Stable currency id, should be AUSD
 	type StableCurrencyId: Get<CurrencyId>;
 
+	/// Stable currency ids to route the swap to native currency through,
+	/// `StableCurrencyId` is always tried first.
+	type StableCurrencyIds: Get<Vec<CurrencyId>>;
+
 	/// Currency to transfer, reserve/unreserve, lock/unlock assets
 	type Currency: MultiLockableCurrency<Self::AccountId, Moment = Self::BlockNumber, CurrencyId = CurrencyId, Balance = Balance>
 		+ MultiReservableCurrency<Self::AccountId, CurrencyId = CurrencyId, Balance = Balance>;
@@ -71,139 +375,2287 @@ pub trait Trait: system::Trait + pallet_transaction_payment::Trait + orml_curren
 
 	/// Handler to trigger events when opening accounts
 
-	/// Handler for the unbalanced reduction when taking transaction fees. This
-	/// is either one or two separate imbalances, the first is the transaction
-	/// fee paid, the second is the tip paid, if any.
+	/// Handler for the unbalanced reduction when taking transaction fees. This
+	/// is either one or two separate imbalances, the first is the transaction
+	/// fee paid, the second is the tip paid, if any.
+
+	/// Event handler which calls when open account in system.
+	type OnCreatedAccount: Happened<Self::AccountId>;
+
+	/// Event handler which calls when the account is opened automatically by
+	/// swapping the received non-native currency with DEX, along with the
+	/// swapped currency. `OnCreatedAccount` is still called.
+	type OnAccountOpenedViaSwap: Happened<(Self::AccountId, CurrencyId)>;
+
+	/// Handler to kill account in system.
+	type KillAccount: Happened<Self::AccountId>;
+
+	/// Event handler which calls when the account is killed, along with the
+	/// reason. It's called besides `KillAccount`.
+	type OnKillAccount: Happened<(Self::AccountId, KillReason)>;
+
+	/// The initial deposit for opening account, would be reserved until
+	/// account closed. The actual deposit is kept in storage and can be
+	/// updated by `UpdateOrigin`.
+	type NewAccountDeposit: Get<Balance>;
+
+	/// The new account which can not afford the open account deposit is kept
+	/// alive without deposit if its free native balance is not below this
+	/// threshold, otherwise it is reaped as dust.
+	type DustThreshold: Get<Balance>;
+
+	/// The portion of the swept native dust which is burned, the remainder
+	/// goes to treasury.
+	type DustBurnRatio: Get<Ratio>;
+
+	/// The currency in which the open account deposit is reserved, the
+	/// deposit is converted from `NewAccountDeposit` with DEX if it's not
+	/// native currency.
+	type DepositCurrencyId: Get<CurrencyId>;
+
+	/// The open account deposit in non-native `DepositCurrencyId`, reserved
+	/// when DEX can not convert it. None means the account can not be opened
+	/// with the non-native deposit without DEX.
+	type NonNativeDeposit: Get<Option<Balance>>;
+
+	/// Discount of the open account deposit, e.g. for accounts which have
+	/// bonded native currency in staking.
+	type OpenDepositDiscount: OpenDepositDiscount<Self::AccountId>;
+
+	/// The treasury module account id to recycle assets.
+	type TreasuryModuleId: Get<ModuleId>;
+
+	/// The max number of source accounts in a single consolidation.
+	type MaxConsolidateSources: Get<u32>;
+
+	/// The period in blocks during which a requested close is retried,
+	/// expired requests are dropped.
+	type PendingCloseWindow: Get<Self::BlockNumber>;
+
+	/// The max number of pending close requests.
+	type MaxPendingCloses: Get<u32>;
+
+	/// The native currency reserved by a close request, returned when the
+	/// account is closed or the request expires.
+	type CloseRequestDeposit: Get<Balance>;
+
+	/// The max number of pending close requests tried in a block.
+	type MaxClosesPerBlock: Get<u32>;
+
+	/// The non-native currencies whose reserved balance can be unreserved to
+	/// pay fee when free balances are insufficient, and the floor of reserved
+	/// balance that must be kept.
+	type FeeReserveFloors: Get<Vec<(CurrencyId, Balance)>>;
+
+	/// The minimum supply amount of the non-native currencies swapped to pay
+	/// fee, the currency is skipped if the required supply is below it.
+	type MinSwapAmounts: Get<Vec<(CurrencyId, Balance)>>;
+
+	/// The verbosity level of events deposited by this module.
+	type EventVerbosity: Get<EventLevel>;
+
+	/// The minimum blocks between two changes of fee currency preference.
+	type FeePreferenceCooldown: Get<Self::BlockNumber>;
+
+	/// The minimum blocks to retry swapping to open account automatically
+	/// after a failed attempt.
+	type OpenAttemptCooldown: Get<Self::BlockNumber>;
+
+	/// The policy to veto transferring currencies when closing account.
+	type TransferGuard: TransferGuard<Self::AccountId>;
+
+	/// The references of account registered by other modules.
+	type AccountReferences: AccountReferences<Self::AccountId>;
+
+	/// The priority of transactions which pay zero fee.
+	type ZeroFeePriority: Get<TransactionPriority>;
+
+	/// The blocks that a cached fee swap path keeps fresh.
+	type FeePathCacheLifetime: Get<Self::BlockNumber>;
+
+	/// The portion of base fee credited to the referrer of the payer, which
+	/// is taken from the portion handled by `OnTransactionPayment`.
+	type ReferralShare: Get<Perbill>;
+
+	/// The max total referral payouts in an era of `EraProvider`.
+	type MaxReferralPayoutPerEra: Get<Balance>;
+
+	/// The call-aware policy of charging fee.
+	type CallFeePolicy: CallFeePolicy<Self::Call, PalletBalanceOf<Self>>;
+
+	/// Resolve the multisig account approved by the call, whose fee is
+	/// charged to the payer registered by the multisig.
+	type MultisigApproval: MultisigApproval<Self::AccountId, Self::Call>;
+
+	/// The locked native balance which can be released to pay transaction
+	/// fee, see `FeeLockAllowance` for the security implications.
+	type FeeLockAllowance: FeeLockAllowance<Self::AccountId>;
+
+	/// The strategy to order the non-native currencies tried to pay fee, the
+	/// preferred fee currency of the account is always tried first.
+	type FeeCurrencyOrder: FeeOrderStrategy<Self>;
+
+	/// The accounts which are never opened by swapping the received non-native
+	/// currency, e.g. module accounts. The treasury account is always excluded.
+	type ExcludedFromAutoOpen: Get<Vec<Self::AccountId>>;
+
+	/// The number of transactions of each account free of fee in an era.
+	type FreeQuotaPerEra: Get<u32>;
+
+	/// The current era of the free quota and the referral payouts cap.
+	type EraProvider: Get<EraIndex>;
+
+	/// The origin which may update the fee swap paths.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The origin which may close accounts in batch.
+	type CloseOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The origin which may force close account ignoring its consumers.
+	type ForceCloseOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The max number of accounts closed in a batch.
+	type MaxCloseBatch: Get<u32>;
+
+	/// The subsidy fund which covers the fee shortfall of the fee relief
+	/// calls as the last resort, recording a debt against the account.
+	type FeeReliefFund: Get<ModuleId>;
+
+	/// Whether the fee relief fund covers fee shortfall.
+	type FeeReliefEnabled: Get<bool>;
+
+	/// The max outstanding fee relief debt of an account.
+	type MaxFeeReliefDebt: Get<Balance>;
+
+	/// The max amount of non-native currency spent on a single fee swap.
+	type MaxFeeSwapAmount: Get<Balance>;
+
+	/// The max hops of the path to swap non-native currency to native currency
+	/// to pay fee, must allow the default path through stable currency.
+	type MaxFeeSwapHops: Get<u32>;
+
+	/// The max number of fee swaps in a block, the fee must be paid in native
+	/// currency once it's reached.
+	type MaxFeeSwapsPerBlock: Get<u32>;
+
+	/// The max number of recently killed accounts kept in `RecentlyKilled`.
+	type MaxRecentlyKilled: Get<u32>;
+
+	/// The signature type used to authorize setting fee preference on
+	/// behalf of the account.
+	type Signature: Verify<Signer = Self::Signer> + Parameter;
+
+	/// The signer of `Signature`.
+	type Signer: IdentifyAccount<AccountId = Self::AccountId>;
+
+	/// The initial max slippage allowed when swap open account deposit or fee
+	/// with DEX, which can be updated by `UpdateOrigin`
+	type MaxSlippageSwapWithDEX: Get<Ratio>;
+
+	/// Find the author of current block, who receives `BlockAuthorShare` of
+	/// transaction fee.
+	type FindAuthor: FindAuthor<Self::AccountId>;
+
+	/// The portion of transaction fee (excluding tip) credited to the block
+	/// author, the remainder goes to `OnTransactionPayment`.
+	type BlockAuthorShare: Get<Perbill>;
+
+	/// Whether the tip goes entirely to the block author.
+	type TipToBlockAuthor: Get<bool>;
+
+	/// Handler for the fee refund which can not be deposited back because
+	/// the account has been reaped during dispatch.
+	type LostRefunds: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Weight information for the extrinsics in this module.
+	type WeightInfo: WeightInfo;
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::BlockNumber,
+	{
+		/// The account is opened. \[who\]
+		AccountOpened(AccountId),
+		/// The account is closed. \[who, recipient\]
+		AccountClosed(AccountId, AccountId),
+		/// The account can not be closed and is skipped in batch. \[who\]
+		CloseAccountSkipped(AccountId),
+		/// The fee refund is lost because the account has been reaped. \[who, refund\]
+		RefundLost(AccountId, Balance),
+		/// Close account is requested, will be closed once it can be reaped. \[who, expire_at\]
+		CloseRequested(AccountId, BlockNumber),
+		/// The close request is expired and dropped. \[who\]
+		CloseRequestExpired(AccountId),
+		/// Swap non-native currency to native to pay fee. \[who, currency_id, supply_amount, native_amount\]
+		FeeSwapped(AccountId, CurrencyId, Balance, Balance),
+		/// The fee swap path of currency is updated. \[currency_id, path\]
+		FeeSwapPathUpdated(CurrencyId, Option<Vec<CurrencyId>>),
+		/// The fee payer approval is updated. \[payer, who, approved\]
+		FeePayerApprovalUpdated(AccountId, AccountId, bool),
+		/// The fee payer of multisig approvals is updated. \[multisig, payer\]
+		MultisigFeePayerUpdated(AccountId, Option<AccountId>),
+		/// The referrer is set. \[who, referrer\]
+		ReferrerSet(AccountId, AccountId),
+		/// The fee currency preference is updated. \[who, currency_id\]
+		FeeCurrencyUpdated(AccountId, Option<CurrencyId>),
+		/// The reserved non-native currencies are unreserved when force closing account. \[who, unreserved\]
+		ReservedForceUnreserved(AccountId, Vec<(CurrencyId, Balance)>),
+		/// The native dust of the account which can not be opened is swept to treasury. \[who, amount\]
+		DustSwept(AccountId, Balance),
+		/// The portion of the native dust of the account which can not be opened is burned. \[who, amount\]
+		DustBurned(AccountId, Balance),
+		/// The total native dust swept to treasury in the block. \[amount\]
+		DustSummary(Balance),
+		/// The deposit for opening account is updated. \[amount\]
+		NewAccountDepositUpdated(Balance),
+		/// The max slippage allowed when swap with DEX is updated. \[slippage\]
+		MaxSlippageUpdated(Ratio),
+		/// The max slippage of currency is updated. \[currency_id, slippage\]
+		CurrencySlippageUpdated(CurrencyId, Option<Ratio>),
+		/// The call is set to be free of fee or not. \[call_index, free\]
+		FeeFreeCallUpdated((u8, u8), bool),
+		/// The call is set to be covered by the fee relief fund or not. \[call_index, relief\]
+		FeeReliefCallUpdated((u8, u8), bool),
+		/// The call is set to be allowed to kill the account by paying fee or not. \[call_index, allow_death\]
+		AllowDeathCallUpdated((u8, u8), bool),
+		/// The multiplier of the base fee of the call is updated. \[call_index, multiplier\]
+		CallFeeMultiplierUpdated((u8, u8), Option<Ratio>),
+		/// The fee shortfall is covered by the fee relief fund. \[who, amount\]
+		FeeRelieved(AccountId, Balance),
+		/// The fee relief debt is repaid to the fee relief fund. \[who, amount\]
+		FeeReliefRepaid(AccountId, Balance),
+		/// The free balances of currencies are swept to recipient. \[who, recipient, currencies\]
+		CurrenciesSwept(AccountId, AccountId, Vec<CurrencyId>),
+		/// The account is force closed by governance, ignoring its consumers. \[who, recipient\]
+		AccountForceClosed(AccountId, AccountId),
+		/// The final fee charged for a transaction, including tip, in native currency or swapped from the currency. \[who, block_number, call_index, native_fee, currency_id, currency_amount\]
+		FeeCharged(AccountId, BlockNumber, (u8, u8), Balance, CurrencyId, Balance),
+		/// The fees collected by currency are reset.
+		FeesCollectedReset,
+		/// The open account deposit is funded by swapping the non-native currency. \[who, currency_id, supply_amount, native_amount\]
+		DepositFundedViaSwap(AccountId, CurrencyId, Balance, Balance),
+	}
+);
+
+decl_error! {
+	/// Error for accounts manager module.
+	pub enum Error for Module<T: Trait> {
+		/// Balance is not sufficient
+		NotEnoughBalance,
+		/// Account has consumers, e.g. non-zero ref count
+		HasConsumers,
+		/// Account still has active reserved(include non-native token and native token beyond new account deposit)
+		StillHasActiveReserved,
+		/// Too many source accounts to consolidate
+		TooManySources,
+		/// Too many accounts to close in a batch
+		TooManyAccounts,
+		/// The destination of consolidation is one of the sources
+		InvalidDestination,
+		/// The origin is not the consolidation owner of source account
+		NotConsolidationOwner,
+		/// The account already has a pending close request
+		AlreadyRequestedClose,
+		/// Too many pending close requests
+		TooManyPendingCloses,
+		/// The currency is not allowed to pay fee
+		InvalidFeeCurrency,
+		/// The signature is invalid
+		InvalidSignature,
+		/// The fee currency preference is changed too recently
+		PreferenceChangedTooRecently,
+		/// The transfer of currency is vetoed when closing account
+		TransferVetoed,
+		/// There's no available path to swap the currency to native currency
+		NoAvailableFeePath,
+		/// The fee swap path is invalid
+		InvalidFeeSwapPath,
+		/// The referrer is invalid
+		InvalidReferrer,
+		/// The recipient does not exist and can not be opened by the transferred currencies
+		RecipientCannotReceive,
+		/// The account has been opened with deposit already
+		AccountAlreadyOpened,
+		/// Neither enough native currency nor available swap to afford the open account deposit
+		CannotOpenAccount,
+		/// Account has providers registered by other modules
+		HasProviders,
+		/// Native currency can not be swept, close account instead
+		CannotSweepNativeCurrency,
+		/// The module id can not be converted to a module account
+		InvalidModuleAccount,
+		/// The fee is swapped into native currency, but still can not be withdrawn
+		FeeWithdrawBlocked,
+		/// The fee payer has not approved to pay for the account
+		FeePayerNotApproved,
+		/// The max number of fee swaps in current block is reached
+		FeeSwapLimitReached,
+		/// The account has been closed already
+		AccountAlreadyClosed,
+		/// The fee swap path has more hops than `MaxFeeSwapHops`
+		PathTooLong,
+		/// The fee relief debt can not be repaid by the native currency of the account
+		FeeReliefDebtOutstanding,
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Accounts {
+		/// The open account deposit actually reserved for the account.
+		/// Accounts opened without record reserved the full `NewAccountDeposit`.
+		pub OpenDeposits get(fn open_deposits): map hasher(twox_64_concat) T::AccountId => Option<Balance>;
+
+		/// The currency in which the open account deposit is reserved for the account.
+		/// Accounts without record reserved the deposit in native currency.
+		pub DepositCurrencies get(fn deposit_currencies): map hasher(twox_64_concat) T::AccountId => Option<CurrencyId>;
+
+		/// The account allowed to consolidate the account into another account.
+		pub ConsolidationOwner get(fn consolidation_owner): map hasher(twox_64_concat) T::AccountId => Option<T::AccountId>;
+
+		/// The pending close requests, retried with backoff until expired.
+		pub PendingCloses get(fn pending_closes): Vec<(T::AccountId, CloseRequest<T::AccountId, T::BlockNumber>)>;
+
+		/// The native currency reserved by the pending close request of the account.
+		pub CloseRequestDeposits get(fn close_request_deposits): map hasher(twox_64_concat) T::AccountId => Option<Balance>;
+
+		/// The preferred non-native currency to pay fee, which is tried first
+		/// if native currency is not enough.
+		pub FeeCurrency get(fn fee_currency): map hasher(twox_64_concat) T::AccountId => Option<CurrencyId>;
+
+		/// The nonce of signed payload to set fee currency, used to prevent replay.
+		pub FeeCurrencyNonce get(fn fee_currency_nonce): map hasher(twox_64_concat) T::AccountId => u32;
+
+		/// The block number at which the fee currency preference is changed last time.
+		pub FeeCurrencyChangedAt get(fn fee_currency_changed_at): map hasher(twox_64_concat) T::AccountId => Option<T::BlockNumber>;
+
+		/// The custom paths to swap non-native currency to native currency to pay fee.
+		pub FeeSwapPath get(fn custom_fee_swap_path): map hasher(twox_64_concat) CurrencyId => Option<Vec<CurrencyId>>;
+
+		/// The accounts opted out of swapping non-native currencies to pay fee automatically,
+		/// which is reset when the account is closed.
+		pub NoAutoSwap get(fn no_auto_swap): map hasher(twox_64_concat) T::AccountId => bool;
+
+		/// The fee payer approvals, the first account approves to pay fee for the second account.
+		pub FeePayerApprovals get(fn fee_payer_approvals): double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) T::AccountId => bool;
+
+		/// The account to pay fee for the approvals of the multisig account, which
+		/// must have approved to pay for the multisig account.
+		pub MultisigFeePayers get(fn multisig_fee_payer): map hasher(twox_64_concat) T::AccountId => Option<T::AccountId>;
+
+		/// The recently killed accounts and the block number at which they are killed,
+		/// the oldest is evicted when it's full.
+		pub RecentlyKilled get(fn recently_killed): Vec<(T::AccountId, T::BlockNumber)>;
+
+		/// The referrer of account, who receives `ReferralShare` of the fee paid by the account.
+		pub Referrer get(fn referrer): map hasher(twox_64_concat) T::AccountId => Option<T::AccountId>;
+
+		/// The era index and the total referral payouts in the era.
+		pub ReferralPayouts get(fn referral_payouts): (EraIndex, Balance);
+
+		/// The cached path to swap non-native currency to native currency to pay fee,
+		/// and the block number at which it is cached.
+		pub CachedFeePath get(fn cached_fee_path): double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) CurrencyId => Option<(Vec<CurrencyId>, T::BlockNumber)>;
+
+		/// The total native dust swept to treasury in current block, reset on finalize.
+		pub SweptDust get(fn swept_dust): Balance;
+
+		/// The total native dust swept to treasury ever.
+		pub TotalSweptDust get(fn total_swept_dust): Balance;
+
+		/// The number of fee swaps in current block, reset on finalize.
+		pub SwapsThisBlock get(fn swaps_this_block): u32;
+
+		/// The total fees collected in the currency in current accounting period,
+		/// counted in the currency before swapping to native currency.
+		pub FeesCollectedByCurrency get(fn fees_collected): map hasher(twox_64_concat) CurrencyId => Balance;
+
+		/// The era and the number of transactions of the account free of fee in
+		/// the era, the number is reset once the era changes.
+		pub FreeQuotaUsed get(fn free_quota_used): map hasher(twox_64_concat) T::AccountId => (EraIndex, u32);
+
+		/// The deposit for opening account, `NewAccountDeposit` if not set yet.
+		pub NewAccountDepositAmount get(fn new_account_deposit): Balance = T::NewAccountDeposit::get();
+
+		/// The max slippage allowed when swap with DEX, `MaxSlippageSwapWithDEX` if not set yet.
+		pub MaxSlippage get(fn max_slippage): Ratio = T::MaxSlippageSwapWithDEX::get();
+
+		/// The max slippage allowed when swap the currency with DEX, overrides `MaxSlippage`.
+		pub CurrencySlippage get(fn currency_slippage): map hasher(twox_64_concat) CurrencyId => Option<Ratio>;
+
+		/// The calls free of transaction fee, keyed by (module index, call index).
+		pub FeeFreeCalls get(fn fee_free_calls): map hasher(twox_64_concat) (u8, u8) => bool;
+
+		/// The calls whose fee shortfall can be covered by the fee relief fund,
+		/// keyed by (module index, call index).
+		pub FeeReliefCalls get(fn fee_relief_calls): map hasher(twox_64_concat) (u8, u8) => bool;
+
+		/// The calls which are allowed to kill the account by paying fee, keyed by
+		/// (module index, call index). Other calls keep the account alive.
+		pub AllowDeathCalls get(fn allow_death_calls): map hasher(twox_64_concat) (u8, u8) => bool;
+
+		/// The multipliers of the base fee of the calls, keyed by (module index, call index).
+		/// The calls not listed pay the base fee as is.
+		pub CallFeeMultiplier get(fn call_fee_multiplier): map hasher(twox_64_concat) (u8, u8) => Option<Ratio>;
+
+		/// The outstanding fee relief debt of the account, which is repaid with
+		/// the native currency left after paying fee of later transactions, or
+		/// when the account is closed. It is kept if the account is reaped.
+		pub FeeReliefDebts get(fn fee_relief_debts): map hasher(twox_64_concat) T::AccountId => Balance;
+
+		/// The block number of the last failed attempt to open the account automatically.
+		pub LastOpenAttempt get(fn last_open_attempt): map hasher(twox_64_concat) T::AccountId => Option<T::BlockNumber>;
+
+		/// The accounts whose last failed open attempt expires at the block, which are
+		/// removed from `LastOpenAttempt` in `on_initialize` of the block.
+		pub OpenAttemptExpiries get(fn open_attempt_expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<T::AccountId>;
+
+		/// The accounts being opened by swapping in `on_received`. The flag is set only for
+		/// the duration of the swap, and nested `on_received` of the flagged account triggered
+		/// by the swap is short-circuited.
+		pub SwappingToOpen get(fn swapping_to_open): map hasher(twox_64_concat) T::AccountId => bool;
+	}
+
+	add_extra_genesis {
+		config(initial_accounts): Vec<(T::AccountId, Option<CurrencyId>)>;
+
+		build(|config: &GenesisConfig<T>| {
+			config.initial_accounts.iter().for_each(|(who, fee_currency)| {
+				// the accounts endowed with native currency by `pallet_balances` are opened already
+				if !<Module<T> as StoredMap<_, _>>::is_explicit(who) {
+					system::Account::<T>::mutate(who, |_| {});
+					Module::<T>::open_account(who);
+				}
+				// the unfunded account is swept instead of opened, which is a misconfiguration
+				assert!(
+					<Module<T> as StoredMap<_, _>>::is_explicit(who),
+					"initial account must be funded to be opened"
+				);
+				if let Some(currency_id) = fee_currency {
+					FeeCurrency::<T>::insert(who, currency_id);
+				}
+			});
+		})
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+		fn deposit_event() = default;
+
+		/// All non-native currency ids in Acala.
+		const AllNonNativeCurrencyIds: Vec<CurrencyId> = T::AllNonNativeCurrencyIds::get();
+
+		/// Native currency id, the actual received currency type as fee for treasury.
+		const NativeCurrencyId: CurrencyId = T::NativeCurrencyId::get();
+
+		/// Stable currency id.
+		const StableCurrencyId: CurrencyId = T::StableCurrencyId::get();
+
+		/// The initial deposit for opening account.
+		const NewAccountDeposit: Balance = T::NewAccountDeposit::get();
+
+		/// The threshold of free native balance to keep new account alive without deposit.
+		const DustThreshold: Balance = T::DustThreshold::get();
+
+		/// The currency in which the open account deposit is reserved.
+		const DepositCurrencyId: CurrencyId = T::DepositCurrencyId::get();
+
+		/// The open account deposit in non-native currency, reserved when DEX
+		/// can not convert it.
+		const NonNativeDeposit: Option<Balance> = T::NonNativeDeposit::get();
+
+		/// The treasury module account id to recycle assets.
+		const TreasuryModuleId: ModuleId = T::TreasuryModuleId::get();
+
+		/// The max number of source accounts in a single consolidation.
+		const MaxConsolidateSources: u32 = T::MaxConsolidateSources::get();
+
+		/// The period in blocks during which a requested close is retried.
+		const PendingCloseWindow: T::BlockNumber = T::PendingCloseWindow::get();
+
+		/// The max number of pending close requests.
+		const MaxPendingCloses: u32 = T::MaxPendingCloses::get();
+
+		/// The native currency reserved by a close request.
+		const CloseRequestDeposit: Balance = T::CloseRequestDeposit::get();
+
+		/// The max number of pending close requests tried in a block.
+		const MaxClosesPerBlock: u32 = T::MaxClosesPerBlock::get();
+
+		/// Initialize the new account deposit from `NewAccountDeposit` and the max
+		/// slippage from `MaxSlippageSwapWithDEX` on first run
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(2);
+			if !NewAccountDepositAmount::exists() {
+				NewAccountDepositAmount::put(T::NewAccountDeposit::get());
+				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+			}
+			if !MaxSlippage::exists() {
+				MaxSlippage::put(T::MaxSlippageSwapWithDEX::get());
+				weight = weight.saturating_add(T::DbWeight::get().writes(1));
+			}
+			weight
+		}
+
+		/// Try to complete pending close requests
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			Self::prune_open_attempts(now).saturating_add(Self::process_pending_closes(now))
+		}
+
+		fn on_finalize(_now: T::BlockNumber) {
+			SwapsThisBlock::kill();
+
+			let swept = SweptDust::take();
+			if !swept.is_zero() {
+				Self::deposit_event_at(EventLevel::Normal, RawEvent::DustSummary(swept));
+			}
+		}
+
+		fn integrity_test() {
+			// the default path `[currency, stable, native]` has 2 hops
+			assert!(T::MaxFeeSwapHops::get() >= 2, "MaxFeeSwapHops must allow the default fee swap path");
+		}
+
+		/// The initial max slippage allowed when swap open account deposit or fee with DEX
+		const MaxSlippageSwapWithDEX: Ratio = T::MaxSlippageSwapWithDEX::get();
+
+		/// The portion of transaction fee credited to the block author.
+		const BlockAuthorShare: Perbill = T::BlockAuthorShare::get();
+
+		/// Whether the tip goes entirely to the block author.
+		const TipToBlockAuthor: bool = T::TipToBlockAuthor::get();
+
+		/// The priority of transactions which pay zero fee.
+		const ZeroFeePriority: TransactionPriority = T::ZeroFeePriority::get();
+
+		/// The portion of base fee credited to the referrer of the payer.
+		const ReferralShare: Perbill = T::ReferralShare::get();
+
+		/// The max total referral payouts in an era.
+		const MaxReferralPayoutPerEra: Balance = T::MaxReferralPayoutPerEra::get();
+
+		/// The max number of accounts closed in a batch.
+		const MaxCloseBatch: u32 = T::MaxCloseBatch::get();
+
+		/// The max amount of non-native currency spent on a single fee swap.
+		const MaxFeeSwapAmount: Balance = T::MaxFeeSwapAmount::get();
+
+		/// The max hops of the path to swap non-native currency to native currency to pay fee.
+		const MaxFeeSwapHops: u32 = T::MaxFeeSwapHops::get();
+
+		/// The max number of fee swaps in a block.
+		const MaxFeeSwapsPerBlock: u32 = T::MaxFeeSwapsPerBlock::get();
+
+		/// The max number of recently killed accounts kept in `RecentlyKilled`.
+		const MaxRecentlyKilled: u32 = T::MaxRecentlyKilled::get();
+
+		/// The blocks that a cached fee swap path keeps fresh.
+		const FeePathCacheLifetime: T::BlockNumber = T::FeePathCacheLifetime::get();
+
+		/// The minimum blocks between two changes of fee currency preference.
+		const FeePreferenceCooldown: T::BlockNumber = T::FeePreferenceCooldown::get();
+
+		/// The minimum blocks to retry swapping to open account automatically after a failed attempt.
+		const OpenAttemptCooldown: T::BlockNumber = T::OpenAttemptCooldown::get();
+
+		/// The number of transactions of each account free of fee in an era.
+		const FreeQuotaPerEra: u32 = T::FreeQuotaPerEra::get();
+
+		/// Kill self account from system.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `recipient`: the account as recipient to receive remaining currencies of the account will be killed,
+		///					None means no recipient is specified.
+		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32)]
+		pub fn close_account(origin, recipient: Option<T::AccountId>) {
+			let who = ensure_signed(origin)?;
+			// fail cleanly on the double submission
+			ensure!(<Self as StoredMap<_, _>>::is_explicit(&who), Error::<T>::AccountAlreadyClosed);
+			with_transaction_result(|| Self::do_close_account(&who, recipient, false))?;
+		}
+
+		/// Kill self account from system, all balances of the account are transferred
+		/// to `recipient`, which is guaranteed to be opened with the transferred
+		/// native currency before the account is killed.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `recipient`: the account as recipient to receive remaining currencies of the account,
+		///					opened with the open account deposit if it does not exist.
+		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32)]
+		pub fn close_account_to_new(origin, recipient: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			ensure!(<Self as StoredMap<_, _>>::is_explicit(&who), Error::<T>::AccountAlreadyClosed);
+			with_transaction_result(|| {
+				if !<Self as StoredMap<_, _>>::is_explicit(&recipient) {
+					let native_currency_id = T::NativeCurrencyId::get();
+					let total_native = <T as Trait>::Currency::total_balance(native_currency_id, &who);
+					ensure!(
+						total_native >= Self::open_deposit_of(&recipient),
+						Error::<T>::RecipientCannotReceive,
+					);
+				}
+				Self::do_close_account(&who, Some(recipient.clone()), false)?;
+				ensure!(
+					<Self as StoredMap<_, _>>::is_explicit(&recipient),
+					Error::<T>::RecipientCannotReceive,
+				);
+				Ok(())
+			})?;
+		}
+
+		/// Kill self account from system, all free balances of the account are swapped
+		/// to `target_currency` with DEX before transferred to recipient. The balances
+		/// which can not be swapped under the slippage limit are transferred as-is,
+		/// and the open account deposit is returned in native currency.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `target_currency`: the currency to receive remaining balances of the account.
+		/// - `recipient`: the account as recipient to receive remaining currencies of the account will be killed,
+		///					None means no recipient is specified.
+		#[weight = <T as Trait>::WeightInfo::close_account_to_currency(T::AllNonNativeCurrencyIds::get().len() as u32)]
+		pub fn close_account_to_currency(origin, target_currency: CurrencyId, recipient: Option<T::AccountId>) {
+			let who = ensure_signed(origin)?;
+			ensure!(<Self as StoredMap<_, _>>::is_explicit(&who), Error::<T>::AccountAlreadyClosed);
+			with_transaction_result(|| {
+				Self::swap_all_to_currency(&who, target_currency);
+				Self::do_close_account(&who, recipient, false)
+			})?;
+		}
+
+		/// Transfer the free balances of `currencies` of self account to recipient,
+		/// without killing the account or touching the open account deposit.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `currencies`: the non-native currencies to sweep.
+		/// - `recipient`: the account as recipient to receive the swept currencies.
+		#[weight = <T as Trait>::WeightInfo::sweep_currencies(currencies.len() as u32)]
+		pub fn sweep_currencies(origin, currencies: Vec<CurrencyId>, recipient: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				!currencies.contains(&T::NativeCurrencyId::get()),
+				Error::<T>::CannotSweepNativeCurrency,
+			);
+			with_transaction_result(|| {
+				for currency_id in currencies.iter() {
+					Self::guarded_transfer(*currency_id, &who, &recipient)?;
+				}
+				Ok(())
+			})?;
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::CurrenciesSwept(who, recipient, currencies));
+		}
+
+		/// Try to open self account again, after the failed attempt on receiving
+		/// currencies. The open account deposit is reserved from free native
+		/// currency, or swapped from held non-native currencies with DEX if not enough.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `referrer`: the account receives `ReferralShare` of the fee paid by self account,
+		///   which is recorded once the account is opened.
+		#[weight = <T as Trait>::WeightInfo::try_open_account()]
+		pub fn try_open_account(origin, referrer: Option<T::AccountId>) {
+			let who = ensure_signed(origin)?;
+			ensure!(referrer.as_ref() != Some(&who), Error::<T>::InvalidReferrer);
+			with_transaction_result(|| Self::do_try_open_account(&who))?;
+
+			if let Some(referrer) = referrer {
+				Referrer::<T>::insert(&who, &referrer);
+				Self::deposit_event_at(EventLevel::Normal, RawEvent::ReferrerSet(who, referrer));
+			}
+		}
+
+		/// Request to close self account, the account will be closed automatically
+		/// once it can be reaped(e.g. locks have been released) within `PendingCloseWindow`.
+		/// `CloseRequestDeposit` is reserved until the account is closed or the
+		/// request expires.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `recipient`: the account as recipient to receive remaining currencies of the account will be killed,
+		///					None means no recipient is specified.
+		#[weight = <T as Trait>::WeightInfo::request_close()]
+		pub fn request_close(origin, recipient: Option<T::AccountId>) {
+			let who = ensure_signed(origin)?;
+			let mut pending_closes = Self::pending_closes();
+			ensure!(
+				!pending_closes.iter().any(|(account, _)| *account == who),
+				Error::<T>::AlreadyRequestedClose,
+			);
+			ensure!(
+				(pending_closes.len() as u32) < T::MaxPendingCloses::get(),
+				Error::<T>::TooManyPendingCloses,
+			);
+
+			let deposit = T::CloseRequestDeposit::get();
+			<T as Trait>::Currency::reserve(T::NativeCurrencyId::get(), &who, deposit)?;
+			CloseRequestDeposits::<T>::insert(&who, deposit);
+
+			let now = <system::Module<T>>::block_number();
+			let expire_at = now.saturating_add(T::PendingCloseWindow::get());
+			pending_closes.push((
+				who.clone(),
+				CloseRequest {
+					recipient,
+					expire_at,
+					retry_at: now,
+					attempts: 0,
+				},
+			));
+			PendingCloses::<T>::put(pending_closes);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::CloseRequested(who, expire_at));
+		}
+
+		/// Set the fee currency preference of self account.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `currency_id`: the preferred non-native currency, None means remove the preference.
+		#[weight = <T as Trait>::WeightInfo::set_fee_currency()]
+		pub fn set_fee_currency(origin, currency_id: Option<CurrencyId>) {
+			let who = ensure_signed(origin)?;
+			Self::do_set_fee_currency(who, currency_id)?;
+		}
+
+		/// Set the custom path to swap `currency_id` to native currency to pay fee.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `currency_id`: the non-native currency.
+		/// - `path`: the path starts with `currency_id` and ends with native currency,
+		///				None means to use the default path. At most `MaxFeeSwapHops` hops.
+		#[weight = <T as Trait>::WeightInfo::set_fee_swap_path()]
+		pub fn set_fee_swap_path(origin, currency_id: CurrencyId, path: Option<Vec<CurrencyId>>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			if let Some(path) = &path {
+				ensure!(
+					path.len() >= 2
+						&& path.first() == Some(&currency_id)
+						&& path.last() == Some(&T::NativeCurrencyId::get()),
+					Error::<T>::InvalidFeeSwapPath,
+				);
+				ensure!(
+					path.len() as u32 - 1 <= T::MaxFeeSwapHops::get(),
+					Error::<T>::PathTooLong,
+				);
+			}
+
+			FeeSwapPath::mutate_exists(currency_id, |maybe_path| *maybe_path = path.clone());
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::FeeSwapPathUpdated(currency_id, path));
+		}
+
+		/// Set the deposit for opening account, the accounts opened before keep
+		/// the deposit reserved at that time.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `amount`: the new deposit for opening account.
+		#[weight = <T as Trait>::WeightInfo::set_new_account_deposit()]
+		pub fn set_new_account_deposit(origin, #[compact] amount: Balance) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			NewAccountDepositAmount::put(amount);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::NewAccountDepositUpdated(amount));
+		}
+
+		/// Set the max slippage allowed when swap with DEX, for the currencies
+		/// without their own limit.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `slippage`: the new max slippage.
+		#[weight = <T as Trait>::WeightInfo::set_max_slippage()]
+		pub fn set_max_slippage(origin, slippage: Ratio) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			MaxSlippage::put(slippage);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::MaxSlippageUpdated(slippage));
+		}
+
+		/// Set the max slippage allowed when swap `currency_id` with DEX.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `currency_id`: the currency to swap.
+		/// - `slippage`: the max slippage, None means to use `MaxSlippage`.
+		#[weight = <T as Trait>::WeightInfo::set_currency_slippage()]
+		pub fn set_currency_slippage(origin, currency_id: CurrencyId, slippage: Option<Ratio>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			CurrencySlippage::mutate_exists(currency_id, |maybe_slippage| *maybe_slippage = slippage);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::CurrencySlippageUpdated(currency_id, slippage));
+		}
+
+		/// Set the call to be free of transaction fee or not.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `call_index`: the (module index, call index) of the call.
+		/// - `free`: whether the call is free of fee.
+		#[weight = <T as Trait>::WeightInfo::set_fee_free_call()]
+		pub fn set_fee_free_call(origin, call_index: (u8, u8), free: bool) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			if free {
+				FeeFreeCalls::insert(call_index, true);
+			} else {
+				FeeFreeCalls::remove(call_index);
+			}
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::FeeFreeCallUpdated(call_index, free));
+		}
+
+		/// Reset the fees collected by currency, at the start of an accounting period.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		#[weight = <T as Trait>::WeightInfo::reset_fees_collected(T::AllNonNativeCurrencyIds::get().len() as u32)]
+		pub fn reset_fees_collected(origin) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			FeesCollectedByCurrency::remove_all();
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::FeesCollectedReset);
+		}
+
+		/// Set the fee shortfall of the call to be covered by the fee relief fund
+		/// or not.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `call_index`: the (module index, call index) of the call.
+		/// - `relief`: whether the call is covered by the fee relief fund.
+		#[weight = <T as Trait>::WeightInfo::set_fee_relief_call()]
+		pub fn set_fee_relief_call(origin, call_index: (u8, u8), relief: bool) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			if relief {
+				FeeReliefCalls::insert(call_index, true);
+			} else {
+				FeeReliefCalls::remove(call_index);
+			}
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::FeeReliefCallUpdated(call_index, relief));
+		}
+
+		/// Allow the call to kill the account by paying fee or not, for the
+		/// terminal operations such as `close_account`.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `call_index`: the (module index, call index) of the call.
+		/// - `allow_death`: whether the fee is withdrawn with `AllowDeath`.
+		#[weight = <T as Trait>::WeightInfo::set_allow_death_call()]
+		pub fn set_allow_death_call(origin, call_index: (u8, u8), allow_death: bool) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			if allow_death {
+				AllowDeathCalls::insert(call_index, true);
+			} else {
+				AllowDeathCalls::remove(call_index);
+			}
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::AllowDeathCallUpdated(call_index, allow_death));
+		}
+
+		/// Set the multiplier of the base fee of the call, to discourage spam of
+		/// the heavy operations.
+		///
+		/// The dispatch origin of this call must be `UpdateOrigin`.
+		///
+		/// - `call_index`: the (module index, call index) of the call.
+		/// - `multiplier`: the multiplier of the base fee, None to remove.
+		#[weight = <T as Trait>::WeightInfo::set_call_fee_multiplier()]
+		pub fn set_call_fee_multiplier(origin, call_index: (u8, u8), multiplier: Option<Ratio>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			match multiplier {
+				Some(multiplier) => CallFeeMultiplier::insert(call_index, multiplier),
+				None => CallFeeMultiplier::remove(call_index),
+			}
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::CallFeeMultiplierUpdated(call_index, multiplier));
+		}
+
+		/// Enable or disable swapping non-native currencies to pay fee automatically
+		/// for self account. If disabled, the transaction fails when native currency
+		/// is not enough to pay fee. It's reset to enabled when the account is closed.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `enabled`: whether to swap non-native currencies to pay fee automatically.
+		#[weight = <T as Trait>::WeightInfo::set_auto_swap()]
+		pub fn set_auto_swap(origin, enabled: bool) {
+			let who = ensure_signed(origin)?;
+			if enabled {
+				NoAutoSwap::<T>::remove(&who);
+			} else {
+				NoAutoSwap::<T>::insert(&who, true);
+			}
+		}
+
+		/// Approve self account to pay the fee of transactions signed by `who`,
+		/// which carry self account as the fee payer.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `who`: the account whose fee is paid by self account.
+		/// - `approved`: approve or revoke.
+		#[weight = <T as Trait>::WeightInfo::approve_fee_payer()]
+		pub fn approve_fee_payer(origin, who: T::AccountId, approved: bool) {
+			let payer = ensure_signed(origin)?;
+			if approved {
+				FeePayerApprovals::<T>::insert(&payer, &who, true);
+			} else {
+				FeePayerApprovals::<T>::remove(&payer, &who);
+			}
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::FeePayerApprovalUpdated(payer, who, approved));
+		}
+
+		/// Set the account to pay the fee of the approvals of self multisig
+		/// account, instead of the approvers.
+		///
+		/// The dispatch origin of this call must be Signed by the multisig
+		/// account.
+		///
+		/// - `payer`: the fee payer, which must have approved to pay for self
+		///   account. None to remove.
+		#[weight = <T as Trait>::WeightInfo::set_multisig_fee_payer()]
+		pub fn set_multisig_fee_payer(origin, payer: Option<T::AccountId>) {
+			let multisig = ensure_signed(origin)?;
+			match &payer {
+				Some(payer) => {
+					ensure!(Self::fee_payer_approvals(payer, &multisig), Error::<T>::FeePayerNotApproved);
+					MultisigFeePayers::<T>::insert(&multisig, payer);
+				}
+				None => MultisigFeePayers::<T>::remove(&multisig),
+			}
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::MultisigFeePayerUpdated(multisig, payer));
+		}
+
+		/// Find the best path to swap `currency_id` to native currency with DEX for
+		/// self account and cache it, which is used to pay fee before it's stale.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `currency_id`: the non-native currency to pay fee.
+		#[weight = <T as Trait>::WeightInfo::cache_fee_path()]
+		pub fn cache_fee_path(origin, currency_id: CurrencyId) {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				T::AllNonNativeCurrencyIds::get().contains(&currency_id),
+				Error::<T>::InvalidFeeCurrency,
+			);
+
+			let path = Self::best_fee_swap_path(&who, currency_id).ok_or(Error::<T>::NoAvailableFeePath)?;
+			CachedFeePath::<T>::insert(&who, currency_id, (path, <system::Module<T>>::block_number()));
+		}
+
+		/// Set the fee currency preference of `who` with the payload signed by `who`,
+		/// which allows relayer to set preference for accounts that hold no fee currency.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `who`: the account whose fee currency preference is set.
+		/// - `currency_id`: the preferred non-native currency, None means remove the preference.
+		/// - `signature`: the signature of `who` over the payload of `fee_currency_payload`.
+		#[weight = <T as Trait>::WeightInfo::set_fee_currency_signed()]
+		pub fn set_fee_currency_signed(
+			origin,
+			who: T::AccountId,
+			currency_id: Option<CurrencyId>,
+			signature: T::Signature,
+		) {
+			ensure_signed(origin)?;
+			let nonce = Self::fee_currency_nonce(&who);
+			let payload = Self::fee_currency_payload(&who, currency_id, nonce);
+			ensure!(signature.verify(&payload[..], &who), Error::<T>::InvalidSignature);
+
+			Self::do_set_fee_currency(who.clone(), currency_id)?;
+			FeeCurrencyNonce::<T>::insert(&who, nonce.saturating_add(1));
+		}
+
+		/// Set the account which is allowed to consolidate self account into another account.
+		///
+		/// The dispatch origin of this call must be Signed.
+		///
+		/// - `owner`: the account allowed to consolidate self account, None means remove the approval.
+		#[weight = <T as Trait>::WeightInfo::set_consolidation_owner()]
+		pub fn set_consolidation_owner(origin, owner: Option<T::AccountId>) {
+			let who = ensure_signed(origin)?;
+			ConsolidationOwner::<T>::mutate_exists(&who, |maybe_owner| *maybe_owner = owner);
+		}
+
+		/// Consolidate multiple accounts owned by origin into `destination`.
+		///
+		/// All balances of sources are transferred to `destination` and sources are killed.
+		/// Sources which can not be closed (non-zero ref count or active reserved) are skipped.
+		///
+		/// The dispatch origin of this call must be Signed, and be the consolidation owner
+		/// of every source.
+		///
+		/// - `sources`: the accounts to be consolidated, at most `MaxConsolidateSources`.
+		/// - `destination`: the account to receive all currencies of sources.
+		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32).saturating_mul(sources.len() as Weight)]
+		pub fn consolidate_accounts(origin, sources: Vec<T::AccountId>, destination: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				sources.len() as u32 <= T::MaxConsolidateSources::get(),
+				Error::<T>::TooManySources,
+			);
+			ensure!(!sources.contains(&destination), Error::<T>::InvalidDestination);
+			for source in sources.iter() {
+				ensure!(
+					Self::consolidation_owner(source).as_ref() == Some(&who),
+					Error::<T>::NotConsolidationOwner,
+				);
+			}
+
+			for source in sources {
+				// skip the source which can not be closed
+				let _ = with_transaction_result(|| Self::do_close_account(&source, Some(destination.clone()), false));
+			}
+		}
+
+		/// Close multiple accounts, all balances of them are transferred to `recipient`.
+		/// Accounts which can not be closed (non-zero ref count or active reserved) are skipped.
+		///
+		/// The dispatch origin of this call must be `CloseOrigin`.
+		///
+		/// - `accounts`: the accounts to be closed, at most `MaxCloseBatch`.
+		/// - `recipient`: the account as recipient to receive remaining currencies of the accounts,
+		///					None means no recipient is specified.
+		/// - `force_unreserve`: unreserve the reserved non-native currencies and transfer them to
+		///					recipient, instead of skipping the accounts which have them.
+		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32).saturating_mul(accounts.len() as Weight)]
+		pub fn close_accounts(origin, accounts: Vec<T::AccountId>, recipient: Option<T::AccountId>, force_unreserve: bool) {
+			T::CloseOrigin::ensure_origin(origin)?;
+			Self::do_close_accounts(accounts, recipient, force_unreserve)?;
+		}
+
+		/// Force close the account ignoring its consumers(e.g. the stuck ref count left
+		/// by a buggy module), all balances of it are transferred to `recipient`.
+		///
+		/// This is an emergency tool for recovery, it must not be used while the account
+		/// has locks which represent real obligations. Locked balances are not transferred,
+		/// so the closing fails in that case, as it does with active reserved.
+		///
+		/// The dispatch origin of this call must be `ForceCloseOrigin`.
+		///
+		/// - `who`: the account to be closed.
+		/// - `recipient`: the account as recipient to receive remaining currencies of the account,
+		///					None means no recipient is specified.
+		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32)]
+		pub fn force_close_account(origin, who: T::AccountId, recipient: Option<T::AccountId>) {
+			T::ForceCloseOrigin::ensure_origin(origin)?;
+			ensure!(<Self as StoredMap<_, _>>::is_explicit(&who), Error::<T>::AccountAlreadyClosed);
+			ensure!(!T::AccountReferences::has_providers(&who), Error::<T>::HasProviders);
+			let recipient = recipient.unwrap_or_else(Self::treasury_account_id);
+			with_transaction_result(|| Self::close_account_unchecked(&who, Some(recipient.clone()), false))?;
+			// deposit at the lowest level for auditability
+			Self::deposit_event_at(EventLevel::Errors, RawEvent::AccountForceClosed(who, recipient));
+		}
+
+		/// Close multiple accounts, all balances of them are transferred to the
+		/// account of `module_id`. Accounts which can not be closed are skipped.
+		///
+		/// The dispatch origin of this call must be `CloseOrigin`.
+		///
+		/// - `accounts`: the accounts to be closed, at most `MaxCloseBatch`.
+		/// - `module_id`: the module whose account receives remaining currencies of the accounts.
+		/// - `force_unreserve`: unreserve the reserved non-native currencies and transfer them to
+		///					the module account, instead of skipping the accounts which have them.
+		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32).saturating_mul(accounts.len() as Weight)]
+		pub fn close_accounts_to_module(origin, accounts: Vec<T::AccountId>, module_id: ModuleId, force_unreserve: bool) {
+			T::CloseOrigin::ensure_origin(origin)?;
+			let recipient: T::AccountId = module_id.into_account();
+			// the account id type must be able to hold the module id
+			ensure!(
+				ModuleId::try_from_account(&recipient) == Some(module_id),
+				Error::<T>::InvalidModuleAccount,
+			);
+			Self::do_close_accounts(accounts, Some(recipient), force_unreserve)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Get treasury account id.
+	pub fn treasury_account_id() -> T::AccountId {
+		T::TreasuryModuleId::get().into_account()
+	}
+
+	/// The account id of the fee relief fund.
+	pub fn fee_relief_account_id() -> T::AccountId {
+		T::FeeReliefFund::get().into_account()
+	}
+
+	/// Cover the shortfall of `fee` from the fee relief fund, and record it as
+	/// the debt of `who`. Return true if the shortfall is covered.
+	fn relieve_fee(who: &T::AccountId, fee: PalletBalanceOf<T>, reason: WithdrawReasons) -> bool {
+		if !T::FeeReliefEnabled::get() {
+			return false;
+		}
+
+		let shortfall: Balance = fee
+			.saturating_sub(Self::usable_native_balance_for(who, reason))
+			.unique_saturated_into();
+		let debt = Self::fee_relief_debts(who).saturating_add(shortfall);
+		if debt > T::MaxFeeReliefDebt::get() {
+			return false;
+		}
+
+		if <T as pallet_transaction_payment::Trait>::Currency::transfer(
+			&Self::fee_relief_account_id(),
+			who,
+			shortfall.unique_saturated_into(),
+			ExistenceRequirement::KeepAlive,
+		)
+		.is_err()
+		{
+			return false;
+		}
+
+		FeeReliefDebts::<T>::insert(who, debt);
+		Self::deposit_event_at(EventLevel::Normal, RawEvent::FeeRelieved(who.clone(), shortfall));
+		true
+	}
+
+	/// Repay the fee relief debt of `who` to the fee relief fund with its
+	/// usable native currency, as much as possible.
+	fn repay_fee_relief_debt(who: &T::AccountId) {
+		let debt = Self::fee_relief_debts(who);
+		if debt.is_zero() {
+			return;
+		}
+
+		let repayment: Balance =
+			debt.min(Self::usable_native_balance_for(who, WithdrawReason::Transfer.into()).unique_saturated_into());
+		if repayment.is_zero()
+			|| <T as pallet_transaction_payment::Trait>::Currency::transfer(
+				who,
+				&Self::fee_relief_account_id(),
+				repayment.unique_saturated_into(),
+				ExistenceRequirement::KeepAlive,
+			)
+			.is_err()
+		{
+			return;
+		}
+
+		if repayment == debt {
+			FeeReliefDebts::<T>::remove(who);
+		} else {
+			FeeReliefDebts::<T>::insert(who, debt - repayment);
+		}
+		Self::deposit_event_at(EventLevel::Normal, RawEvent::FeeReliefRepaid(who.clone(), repayment));
+	}
+
+	/// The DEX trading path to swap non-native `currency_id` to native
+	/// currency, the custom path set by `UpdateOrigin` is preferred.
+	pub fn fee_swap_path(currency_id: CurrencyId) -> Vec<CurrencyId> {
+		Self::custom_fee_swap_path(currency_id)
+			.unwrap_or_else(|| Self::hub_swap_path(currency_id, T::StableCurrencyId::get()))
+	}
+
+	/// The DEX trading paths to swap non-native `currency_id` to native
+	/// currency, through each of the stable currencies with the primary
+	/// `StableCurrencyId` first. Only the custom path if it is set.
+	pub fn fee_swap_paths(currency_id: CurrencyId) -> Vec<Vec<CurrencyId>> {
+		if let Some(path) = Self::custom_fee_swap_path(currency_id) {
+			return vec![path];
+		}
+
+		let mut paths: Vec<Vec<CurrencyId>> = Vec::new();
+		for stable_currency_id in Self::stable_currency_ids() {
+			let path = Self::hub_swap_path(currency_id, stable_currency_id);
+			if !paths.contains(&path) {
+				paths.push(path);
+			}
+		}
+		paths
+	}
+
+	/// The stable currencies used as the hub of swap routing, the primary
+	/// `StableCurrencyId` first.
+	fn stable_currency_ids() -> Vec<CurrencyId> {
+		let primary = T::StableCurrencyId::get();
+		let mut stable_currency_ids = vec![primary];
+		stable_currency_ids.extend(T::StableCurrencyIds::get().into_iter().filter(|id| *id != primary));
+		stable_currency_ids
+	}
+
+	/// The trading path to swap `currency_id` to native currency through
+	/// `stable_currency_id`.
+	fn hub_swap_path(currency_id: CurrencyId, stable_currency_id: CurrencyId) -> Vec<CurrencyId> {
+		let native_currency_id = T::NativeCurrencyId::get();
+		if currency_id == stable_currency_id {
+			vec![stable_currency_id, native_currency_id]
+		} else {
+			vec![currency_id, stable_currency_id, native_currency_id]
+		}
+	}
+
+	/// The trading path to swap `supply_currency_id` to `target_currency_id`,
+	/// through stable currency if neither of them is stable currency.
+	pub fn swap_path(supply_currency_id: CurrencyId, target_currency_id: CurrencyId) -> Vec<CurrencyId> {
+		let stable_currency_id = T::StableCurrencyId::get();
+		if supply_currency_id == stable_currency_id || target_currency_id == stable_currency_id {
+			vec![supply_currency_id, target_currency_id]
+		} else {
+			vec![supply_currency_id, stable_currency_id, target_currency_id]
+		}
+	}
+
+	/// The minimum supply amount of `currency_id` swapped to pay fee, zero if
+	/// not set in `MinSwapAmounts`.
+	fn min_swap_amount_of(currency_id: CurrencyId) -> Balance {
+		T::MinSwapAmounts::get()
+			.into_iter()
+			.find(|(id, _)| *id == currency_id)
+			.map_or_else(Zero::zero, |(_, amount)| amount)
+	}
+
+	/// The max slippage allowed when swap `currency_id` with DEX, the per-currency
+	/// limit if set, otherwise `MaxSlippage`.
+	pub fn slippage_limit_of(currency_id: CurrencyId) -> Ratio {
+		Self::currency_slippage(currency_id).unwrap_or_else(Self::max_slippage)
+	}
+
+	/// The existence requirement of withdrawing fee for `call`, `AllowDeath`
+	/// only for the calls in `AllowDeathCalls`.
+	pub fn fee_existence_requirement<Call: Encode>(call: &Call) -> ExistenceRequirement {
+		if call
+			.using_encoded(Self::call_index_of)
+			.map_or(false, |call_index| Self::allow_death_calls(call_index))
+		{
+			ExistenceRequirement::AllowDeath
+		} else {
+			ExistenceRequirement::KeepAlive
+		}
+	}
+
+	/// The multiplier of the base fee of `call`, one if not set in
+	/// `CallFeeMultiplier`.
+	pub fn call_fee_multiplier_of<Call: Encode>(call: &Call) -> Ratio {
+		call.using_encoded(Self::call_index_of)
+			.and_then(Self::call_fee_multiplier)
+			.unwrap_or_else(Ratio::one)
+	}
+
+	/// The (module index, call index) of the encoded call.
+	pub fn call_index_of(encoded_call: &[u8]) -> Option<(u8, u8)> {
+		match encoded_call {
+			[module_index, call_index, ..] => Some((*module_index, *call_index)),
+			_ => None,
+		}
+	}
+
+	/// Swap all free balances of `who` to `target_currency_id` with DEX,
+	/// ignore the currencies that can not be swapped under the slippage limit.
+	fn swap_all_to_currency(who: &T::AccountId, target_currency_id: CurrencyId) {
+		let mut currency_ids = T::AllNonNativeCurrencyIds::get();
+		currency_ids.insert(0, T::NativeCurrencyId::get());
+
+		for currency_id in currency_ids {
+			let supply_amount = <T as Trait>::Currency::free_balance(currency_id, who);
+			if currency_id == target_currency_id || supply_amount.is_zero() {
+				continue;
+			}
+
+			let _ = with_transaction_result(|| {
+				T::DEX::swap_with_exact_supply(
+					who,
+					&Self::swap_path(currency_id, target_currency_id),
+					supply_amount,
+					Zero::zero(),
+					Some(Self::slippage_limit_of(currency_id)),
+				)
+			});
+		}
+	}
+
+	/// The path which gets the most native currency by swapping all free
+	/// `currency_id` of `who` with DEX, under the slippage limit.
+	fn best_fee_swap_path(who: &T::AccountId, currency_id: CurrencyId) -> Option<Vec<CurrencyId>> {
+		let supply_amount = <T as Trait>::Currency::free_balance(currency_id, who);
+		let price_impact_limit = Some(Self::slippage_limit_of(currency_id));
+		let mut candidate_paths = vec![Self::fee_swap_path(currency_id)];
+		let direct_path = vec![currency_id, T::NativeCurrencyId::get()];
+		if !candidate_paths.contains(&direct_path) {
+			candidate_paths.push(direct_path);
+		}
+
+		candidate_paths
+			.into_iter()
+			.filter_map(|path| {
+				T::DEX::get_swap_target_amount(&path, supply_amount, price_impact_limit).map(|amount| (amount, path))
+			})
+			.max_by_key(|(amount, _)| *amount)
+			.map(|(_, path)| path)
+	}
+
+	/// The path to swap `currency_id` to native currency to pay fee for `who`,
+	/// use the cached path if it's still fresh.
+	fn fee_swap_path_of(who: &T::AccountId, currency_id: CurrencyId) -> Vec<CurrencyId> {
+		match Self::cached_fee_path(who, currency_id) {
+			Some((path, cached_at))
+				if <system::Module<T>>::block_number() < cached_at.saturating_add(T::FeePathCacheLifetime::get()) =>
+			{
+				path
+			}
+			_ => Self::fee_swap_path(currency_id),
+		}
+	}
+
+	/// The paths to try in order to swap `currency_id` to native currency to
+	/// pay fee for `who`, only the cached path if it's still fresh.
+	fn fee_swap_paths_of(who: &T::AccountId, currency_id: CurrencyId) -> Vec<Vec<CurrencyId>> {
+		match Self::cached_fee_path(who, currency_id) {
+			Some((path, cached_at))
+				if <system::Module<T>>::block_number() < cached_at.saturating_add(T::FeePathCacheLifetime::get()) =>
+			{
+				vec![path]
+			}
+			_ => Self::fee_swap_paths(currency_id),
+		}
+	}
+
+	/// Swap `refund` native currency of `who` back to `currency_id` with DEX.
+	///
+	/// The fee is paid by swapping `supply_amount` of `currency_id` to
+	/// `native_amount`, the refund must not be swapped back at a rate worse
+	/// than that beyond the slippage limit of `currency_id`, otherwise the
+	/// refund is kept in native currency. Return the refund in `currency_id`.
+	fn swap_refund_back(
+		who: &T::AccountId,
+		currency_id: CurrencyId,
+		refund: Balance,
+		supply_amount: Balance,
+		native_amount: Balance,
+	) -> Balance {
+		if refund.is_zero() {
+			return Zero::zero();
+		}
+
+		let slippage_limit = Self::slippage_limit_of(currency_id);
+		let min_target_amount = Ratio::checked_from_rational(supply_amount, native_amount)
+			.map(|rate| {
+				Ratio::one()
+					.saturating_sub(slippage_limit)
+					.saturating_mul(rate)
+					.saturating_mul_int(refund)
+			})
+			.unwrap_or_else(Zero::zero);
+
+		let mut trading_path = Self::fee_swap_path(currency_id);
+		trading_path.reverse();
+		with_transaction_result(|| {
+			T::DEX::swap_with_exact_supply(who, &trading_path, refund, min_target_amount, Some(slippage_limit))
+		})
+		.unwrap_or_else(|_| Zero::zero())
+	}
+
+	/// The native amount can be got by swapping all free `currency_id` of
+	/// `who` with DEX, under the slippage limit.
+	pub fn native_value_of(who: &T::AccountId, currency_id: CurrencyId) -> Balance {
+		let supply_amount = <T as Trait>::Currency::free_balance(currency_id, who);
+		if supply_amount.is_zero() {
+			return Zero::zero();
+		}
+
+		T::DEX::get_swap_target_amount(
+			&Self::fee_swap_path(currency_id),
+			supply_amount,
+			Some(Self::slippage_limit_of(currency_id)),
+		)
+		.unwrap_or_else(Zero::zero)
+	}
+
+	/// The payload signed by `who` to set its fee currency preference with
+	/// `nonce`, which is bound to this chain by the domain tag and the genesis
+	/// hash, so the signature can not be replayed on other chains.
+	pub fn fee_currency_payload(who: &T::AccountId, currency_id: Option<CurrencyId>, nonce: u32) -> Vec<u8> {
+		let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+		(FEE_CURRENCY_PAYLOAD_TAG, genesis_hash, who, currency_id, nonce).encode()
+	}
+
+	/// The max native fee `who` could pay currently.
+	///
+	/// The fee is paid by the usable native, and either the allowed locked
+	/// native or at most one non-native currency, so it is the usable native
+	/// plus the larger of the lock allowance and the max native value of
+	/// non-native currencies, whose supply is capped by `MaxFeeSwapAmount`
+	/// as the fee payment does.
+	pub fn query_max_affordable_fee(who: &T::AccountId) -> Balance {
+		let usable_native = Self::usable_native_balance(who);
+		let max_swappable = if Self::no_auto_swap(who) {
+			Zero::zero()
+		} else {
+			Self::fee_currency_order(who)
+				.into_iter()
+				.map(|currency_id| {
+					let supply_amount =
+						<T as Trait>::Currency::free_balance(currency_id, who).min(T::MaxFeeSwapAmount::get());
+					if supply_amount.is_zero() {
+						return Zero::zero();
+					}
+
+					let price_impact_limit = Some(Self::slippage_limit_of(currency_id));
+					Self::fee_swap_paths_of(who, currency_id)
+						.iter()
+						.filter_map(|path| T::DEX::get_swap_target_amount(path, supply_amount, price_impact_limit))
+						.max()
+						.unwrap_or_else(Zero::zero)
+				})
+				.max()
+				.unwrap_or_else(Zero::zero)
+		};
+
+		usable_native.saturating_add(max_swappable.max(T::FeeLockAllowance::allowance(who)))
+	}
+
+	/// The (currency, free, reserved) balances of `who` in native currency
+	/// and all the non-native currencies, the currencies without any balance
+	/// are excluded unless `include_zero` is true.
+	pub fn all_balances(who: &T::AccountId, include_zero: bool) -> Vec<(CurrencyId, Balance, Balance)> {
+		let mut currency_ids = T::AllNonNativeCurrencyIds::get();
+		currency_ids.insert(0, T::NativeCurrencyId::get());
+
+		currency_ids
+			.into_iter()
+			.map(|currency_id| {
+				(
+					currency_id,
+					<T as Trait>::Currency::free_balance(currency_id, who),
+					<T as Trait>::Currency::reserved_balance(currency_id, who),
+				)
+			})
+			.filter(|(_, free, reserved)| include_zero || !free.is_zero() || !reserved.is_zero())
+			.collect()
+	}
+
+	/// Whether withdrawing `amount` of `currency_id` from the opened account
+	/// `who` would leave it without any balance, that is the remaining
+	/// balances of all currencies are below the existential deposits, so the
+	/// account would be reaped. The open account deposit is reserved, which
+	/// keeps the account alive.
+	pub fn would_reap(who: &T::AccountId, currency_id: CurrencyId, amount: Balance) -> bool {
+		if !<Self as StoredMap<_, _>>::is_explicit(who)
+			|| <T as Trait>::Currency::free_balance(currency_id, who) < amount
+		{
+			return false;
+		}
+
+		Self::all_balances(who, false).into_iter().all(|(id, free, reserved)| {
+			let remaining = free.saturating_add(reserved).saturating_sub(amount);
+			id == currency_id
+				&& (remaining.is_zero() || remaining < <T as Trait>::Currency::minimum_balance(currency_id))
+		})
+	}
+
+	/// Unreserve the minimal amount of non-native currency configured in
+	/// `FeeReserveFloors` and swap it to `native_amount` native currency
+	/// with DEX. The remaining reserved balance must not be below the floor.
+	///
+	/// Return true if swap successfully.
+	fn swap_fee_from_reserved(who: &T::AccountId, native_amount: Balance) -> bool {
+		for (currency_id, floor) in T::FeeReserveFloors::get() {
+			let price_impact_limit = Some(Self::slippage_limit_of(currency_id));
+			let trading_path = Self::fee_swap_path(currency_id);
+			let supply_amount = match T::DEX::get_swap_supply_amount(&trading_path, native_amount, price_impact_limit) {
+				Some(amount) => amount,
+				None => continue,
+			};
+			let free_balance = <T as Trait>::Currency::free_balance(currency_id, who);
+			let unreserve_amount = supply_amount.saturating_sub(free_balance);
+			let remaining_reserved =
+				match <T as Trait>::Currency::reserved_balance(currency_id, who).checked_sub(unreserve_amount) {
+					Some(remaining) => remaining,
+					None => continue,
+				};
+			if remaining_reserved < floor {
+				continue;
+			}
+
+			let result = with_transaction_result(|| {
+				ensure!(
+					<T as Trait>::Currency::unreserve(currency_id, who, unreserve_amount).is_zero(),
+					Error::<T>::NotEnoughBalance,
+				);
+				T::DEX::swap_with_exact_target(
+					who,
+					&trading_path,
+					native_amount,
+					free_balance.saturating_add(unreserve_amount),
+					price_impact_limit,
+				)
+			});
+			if let Ok(supply_amount) = result {
+				Self::deposit_event_at(
+					EventLevel::Verbose,
+					RawEvent::FeeSwapped(who.clone(), currency_id, supply_amount, native_amount),
+				);
+				return true;
+			}
+		}
+
+		false
+	}
+
+	/// Deposit `event` if `level` is enabled by `EventVerbosity`.
+	fn deposit_event_at(level: EventLevel, event: Event<T>) {
+		if level != EventLevel::Off && T::EventVerbosity::get() >= level {
+			Self::deposit_event(event);
+		}
+	}
+
+	/// Get the author of current block.
+	fn block_author() -> Option<T::AccountId> {
+		let digest = <system::Module<T>>::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+		T::FindAuthor::find_author(pre_runtime_digests)
+	}
+
+	/// Distribute the actual fee and tip paid by `who`.
+	///
+	/// `BlockAuthorShare` of fee (and the whole tip if `TipToBlockAuthor`) is
+	/// credited to the block author, `ReferralShare` of fee is credited to
+	/// the referrer of `who` under the era cap, the remainder is handled by
+	/// `OnTransactionPayment`. If the block author can't be found, or the
+	/// referral payout can not create the referrer account, the portion is
+	/// handled by `OnTransactionPayment`.
+	fn distribute_fee(who: &T::AccountId, fee: NegativeImbalanceOf<T>, tip: NegativeImbalanceOf<T>) {
+		let referral_amount = T::ReferralShare::get() * fee.peek();
+		let (fee, tip) = match Self::block_author() {
+			Some(author) => {
+				let author_fee_amount = T::BlockAuthorShare::get() * fee.peek();
+				let (author_fee, fee) = fee.split(author_fee_amount);
+				let (author_imbalance, tip) = if T::TipToBlockAuthor::get() {
+					(author_fee.merge(tip), NegativeImbalanceOf::<T>::zero())
+				} else {
+					(author_fee, tip)
+				};
+				<T as pallet_transaction_payment::Trait>::Currency::resolve_creating(&author, author_imbalance);
+				(fee, tip)
+			}
+			None => (fee, tip),
+		};
+		let fee = match Self::referrer(who) {
+			Some(referrer) => Self::pay_referral(&referrer, fee, referral_amount),
+			None => fee,
+		};
+
+		<T as pallet_transaction_payment::Trait>::OnTransactionPayment::on_unbalanceds(
+			Some(fee).into_iter().chain(Some(tip)),
+		);
+	}
+
+	/// Credit at most `amount` of `fee` to `referrer` under the cap of
+	/// `MaxReferralPayoutPerEra`, return the remainder of `fee`. The payout
+	/// below the existential deposit to a non-existent referrer is not paid,
+	/// which would be burned otherwise.
+	fn pay_referral(
+		referrer: &T::AccountId,
+		fee: NegativeImbalanceOf<T>,
+		amount: PalletBalanceOf<T>,
+	) -> NegativeImbalanceOf<T> {
+		let era = T::EraProvider::get();
+		let (payout_era, paid) = Self::referral_payouts();
+		let paid = if payout_era == era { paid } else { Zero::zero() };
+		let amount: Balance = amount.unique_saturated_into();
+		let amount = amount.min(T::MaxReferralPayoutPerEra::get().saturating_sub(paid));
+		let existential_deposit: Balance =
+			<T as pallet_transaction_payment::Trait>::Currency::minimum_balance().unique_saturated_into();
+		let referrer_exists = !<T as pallet_transaction_payment::Trait>::Currency::total_balance(referrer).is_zero();
+		if amount.is_zero() || (!referrer_exists && amount < existential_deposit) {
+			return fee;
+		}
+
+		let (referral_fee, fee) = fee.split(amount.unique_saturated_into());
+		ReferralPayouts::<T>::put((era, paid.saturating_add(referral_fee.peek().unique_saturated_into())));
+		<T as pallet_transaction_payment::Trait>::Currency::resolve_creating(referrer, referral_fee);
+		fee
+	}
+
+	fn do_set_fee_currency(who: T::AccountId, currency_id: Option<CurrencyId>) -> DispatchResult {
+		if let Some(currency_id) = currency_id {
+			ensure!(
+				T::AllNonNativeCurrencyIds::get().contains(&currency_id),
+				Error::<T>::InvalidFeeCurrency,
+			);
+		}
+
+		let now = <system::Module<T>>::block_number();
+		if let Some(changed_at) = Self::fee_currency_changed_at(&who) {
+			ensure!(
+				now >= changed_at.saturating_add(T::FeePreferenceCooldown::get()),
+				Error::<T>::PreferenceChangedTooRecently,
+			);
+		}
+
+		FeeCurrencyChangedAt::<T>::insert(&who, now);
+		FeeCurrency::<T>::mutate_exists(&who, |maybe_currency_id| *maybe_currency_id = currency_id);
+		Self::deposit_event_at(EventLevel::Normal, RawEvent::FeeCurrencyUpdated(who, currency_id));
+		Ok(())
+	}
+
+	/// The non-native currencies in the order to try to pay fee, the
+	/// preferred fee currency of `who` is the first, then the others in the
+	/// order of `FeeCurrencyOrder`.
+	fn fee_currency_order(who: &T::AccountId) -> Vec<CurrencyId> {
+		let mut currency_ids = T::FeeCurrencyOrder::order(who, T::AllNonNativeCurrencyIds::get());
+		if let Some(fee_currency_id) = Self::fee_currency(who) {
+			if let Some(index) = currency_ids.iter().position(|id| *id == fee_currency_id) {
+				currency_ids.remove(index);
+				currency_ids.insert(0, fee_currency_id);
+			}
+		}
+		currency_ids
+	}
+
+	/// Try to close the accounts of pending close requests which are due, at
+	/// most `MaxClosesPerBlock` in a block. The retry interval of a request
+	/// doubles on each failed attempt, and the tried requests are moved to
+	/// the end of the queue. The expired requests are tried for the last
+	/// time and dropped with the deposit returned.
+	fn process_pending_closes(now: T::BlockNumber) -> Weight {
+		let pending_closes = Self::pending_closes();
+		if pending_closes.is_empty() {
+			return T::DbWeight::get().reads(1);
+		}
+
+		let count = pending_closes.len() as Weight;
+		let mut attempts: u32 = 0;
+		let mut remaining = Vec::with_capacity(pending_closes.len());
+		let mut retried = Vec::new();
+		for (who, mut request) in pending_closes {
+			// the account has been killed by other ways
+			if !<Self as StoredMap<_, _>>::is_explicit(&who) {
+				continue;
+			}
+
+			let expired = now > request.expire_at;
+			let tried = (expired || now >= request.retry_at) && attempts < T::MaxClosesPerBlock::get();
+			if tried {
+				attempts += 1;
+				if with_transaction_result(|| Self::do_close_account(&who, request.recipient.clone(), false)).is_ok() {
+					continue;
+				}
+				let interval = 1u32 << request.attempts.min(MAX_CLOSE_RETRY_EXPONENT);
+				request.retry_at = now.saturating_add(interval.into());
+				request.attempts = request.attempts.saturating_add(1);
+			}
+
+			if expired {
+				if let Some(deposit) = CloseRequestDeposits::<T>::take(&who) {
+					<T as Trait>::Currency::unreserve(T::NativeCurrencyId::get(), &who, deposit);
+				}
+				Self::deposit_event_at(EventLevel::Errors, RawEvent::CloseRequestExpired(who));
+			} else if tried {
+				retried.push((who, request));
+			} else {
+				remaining.push((who, request));
+			}
+		}
+		remaining.append(&mut retried);
+		PendingCloses::<T>::put(remaining);
+
+		<T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32)
+			.saturating_mul(attempts as Weight)
+			.saturating_add(T::DbWeight::get().reads(count))
+			.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+
+	/// Remove the failed open attempts whose cooldown expires at `now`.
+	fn prune_open_attempts(now: T::BlockNumber) -> Weight {
+		let expired = OpenAttemptExpiries::<T>::take(now);
+		let count = expired.len() as Weight;
+		for who in expired {
+			// the account may have attempted again later
+			if Self::last_open_attempt(&who).map_or(false, |last_attempt| {
+				last_attempt.saturating_add(T::OpenAttemptCooldown::get()) <= now
+			}) {
+				LastOpenAttempt::<T>::remove(&who);
+			}
+		}
+		T::DbWeight::get().reads_writes(count.saturating_add(1), count.saturating_add(1))
+	}
+
+	/// Transfer all currencies of `who` to `recipient` and kill `who`.
+	/// Should be called inside a storage transaction.
+	/// Close the account `who`, if `force_unreserve` the reserved non-native
+	/// currencies are unreserved and transferred to recipient too.
+	fn do_close_accounts(
+		accounts: Vec<T::AccountId>,
+		recipient: Option<T::AccountId>,
+		force_unreserve: bool,
+	) -> DispatchResult {
+		ensure!(
+			accounts.len() as u32 <= T::MaxCloseBatch::get(),
+			Error::<T>::TooManyAccounts,
+		);
+
+		for who in accounts {
+			// the closed accounts are skipped rather than closed again
+			if !<Self as StoredMap<_, _>>::is_explicit(&who)
+				|| with_transaction_result(|| Self::do_close_account(&who, recipient.clone(), force_unreserve)).is_err()
+			{
+				Self::deposit_event_at(EventLevel::Errors, RawEvent::CloseAccountSkipped(who));
+			}
+		}
+		Ok(())
+	}
+
+	fn do_close_account(who: &T::AccountId, recipient: Option<T::AccountId>, force_unreserve: bool) -> DispatchResult {
+		Self::can_close_account(who)?;
+		Self::close_account_unchecked(who, recipient, force_unreserve)
+	}
+
+	/// Transfer all balances of `who` to recipient and kill it, without
+	/// checking the consumers and providers of the account.
+	fn close_account_unchecked(
+		who: &T::AccountId,
+		recipient: Option<T::AccountId>,
+		force_unreserve: bool,
+	) -> DispatchResult {
+		let native_currency_id = T::NativeCurrencyId::get();
+
+		// the deposit of the pending close request is returned to be transferred
+		if let Some(deposit) = CloseRequestDeposits::<T>::take(who) {
+			<T as Trait>::Currency::unreserve(native_currency_id, who, deposit);
+		}
+
+		let deposit_currency_id = Self::deposit_currency_of(who);
+		let open_deposit = Self::reserved_open_deposit(who);
+		let native_open_deposit = if deposit_currency_id == native_currency_id {
+			open_deposit
+		} else {
+			Zero::zero()
+		};
+		let total_reserved_native = <T as Trait>::Currency::reserved_balance(native_currency_id, who);
+
+		// ensure total reserved native is lte the reserved open account deposit,
+		// otherwise think the account still has active reserved kept by some bussiness.
+		ensure!(
+			native_open_deposit >= total_reserved_native,
+			Error::<T>::StillHasActiveReserved,
+		);
+
+		// ensure the specified recipient exists or the native transferred is enough to open it,
+		// otherwise the transferred currencies may be lost.
+		if let Some(recipient) = &recipient {
+			let total_native =
+				<T as Trait>::Currency::free_balance(native_currency_id, who).saturating_add(total_reserved_native);
+			ensure!(
+				<Self as StoredMap<_, _>>::is_explicit(recipient)
+					|| total_native >= Self::open_deposit_of(recipient).min(T::DustThreshold::get()),
+				Error::<T>::RecipientCannotReceive,
+			);
+		}
+		let recipient = recipient.unwrap_or_else(Self::treasury_account_id);
+
+		// unreserve all reserved native currency
+		<T as Trait>::Currency::unreserve(native_currency_id, who, total_reserved_native);
+
+		// settle the fee relief debt before the native currency is swept
+		Self::repay_fee_relief_debt(who);
+		ensure!(
+			Self::fee_relief_debts(who).is_zero(),
+			Error::<T>::FeeReliefDebtOutstanding
+		);
+
+		// transfer all free to recipient
+		Self::guarded_transfer(native_currency_id, who, &recipient)?;
+
+		// handle other non-native currencies
+		let mut unreserved: Vec<(CurrencyId, Balance)> = vec![];
+		for currency_id in T::AllNonNativeCurrencyIds::get() {
+			let mut reserved = <T as Trait>::Currency::reserved_balance(currency_id, who);
+
+			// unreserve the open account deposit reserved in non-native currency
+			if currency_id == deposit_currency_id {
+				let deposit = reserved.min(open_deposit);
+				<T as Trait>::Currency::unreserve(currency_id, who, deposit);
+				reserved = reserved.saturating_sub(deposit);
+			}
+
+			if !reserved.is_zero() {
+				// ensure the account has no active reserved of non-native token unless forced
+				ensure!(force_unreserve, Error::<T>::StillHasActiveReserved);
+				let remaining = <T as Trait>::Currency::unreserve(currency_id, who, reserved);
+				unreserved.push((currency_id, reserved.saturating_sub(remaining)));
+			}
+
+			// transfer all free to recipient
+			Self::guarded_transfer(currency_id, who, &recipient)?;
+		}
+
+		if !unreserved.is_empty() {
+			Self::deposit_event_at(
+				EventLevel::Normal,
+				RawEvent::ReservedForceUnreserved(who.clone(), unreserved),
+			);
+		}
+
+		// finally kill the account
+		T::KillAccount::happened(who);
+		T::OnKillAccount::happened(&(who.clone(), KillReason::Closed));
+		Self::deposit_event_at(EventLevel::Normal, RawEvent::AccountClosed(who.clone(), recipient));
+
+		Ok(())
+	}
+
+	/// Check whether the account `who` has no consumers and providers, which
+	/// is required to close the account.
+	pub fn can_close_account(who: &T::AccountId) -> Result<(), Error<T>> {
+		// check must allow death,
+		// if native/non-native currencies has locks, means ref_count shouldn't be zero, can not close the account.
+		ensure!(
+			<system::Module<T>>::allow_death(who) && !T::AccountReferences::has_consumers(who),
+			Error::<T>::HasConsumers,
+		);
+		ensure!(!T::AccountReferences::has_providers(who), Error::<T>::HasProviders);
+		Ok(())
+	}
+
+	/// Ensure `who` can pay `fee` in native currency for `reason`. If the usable
+	/// native currency is not enough, try to use non-native currency to swap
+	/// the gap by exchange with DEX.
+	pub fn ensure_can_charge_fee(
+		who: &T::AccountId,
+		fee: PalletBalanceOf<T>,
+		reason: WithdrawReasons,
+	) -> DispatchResult {
+		Self::do_ensure_can_charge_fee(who, fee, reason).map(|_| ())
+	}
+
+	/// Charge `native_gas_fee` from `who` for EVM gas, swap non-native currency
+	/// with DEX if native currency is not enough. Return the withdrawn imbalance
+	/// for EVM module to deposit.
+	pub fn charge_evm_fee(
+		who: &T::AccountId,
+		native_gas_fee: PalletBalanceOf<T>,
+	) -> Result<NegativeImbalanceOf<T>, DispatchError> {
+		let reason = WithdrawReason::TransactionPayment.into();
+		// swap and withdraw atomically, the swap is reverted if the fee can not be withdrawn
+		with_transaction_result(|| {
+			Self::ensure_can_charge_fee(who, native_gas_fee, reason)?;
+			<T as pallet_transaction_payment::Trait>::Currency::withdraw(
+				who,
+				native_gas_fee,
+				reason,
+				ExistenceRequirement::KeepAlive,
+			)
+		})
+	}
+
+	/// Ensure `who` can pay `fee` in native currency for `reason`, return the
+	/// non-native currency swapped to pay the fee, with the supply amount and
+	/// the native amount of the swap, and the number of currencies tried.
+	fn do_ensure_can_charge_fee(
+		who: &T::AccountId,
+		fee: PalletBalanceOf<T>,
+		reason: WithdrawReasons,
+	) -> Result<(Option<(CurrencyId, Balance, Balance)>, u32), DispatchError> {
+		// check native balance if is enough, zero fee needs no swap
+		let native_is_enough = fee.is_zero()
+			|| <T as pallet_transaction_payment::Trait>::Currency::free_balance(who)
+				.checked_sub(&fee)
+				.map_or(false, |new_free_balance| {
+					<T as pallet_transaction_payment::Trait>::Currency::ensure_can_withdraw(
+						who,
+						fee,
+						reason,
+						new_free_balance,
+					)
+					.is_ok()
+				});
+		if native_is_enough {
+			return Ok((None, 0));
+		}
+
+		// the gap between the fee and the usable native currency
+		let residual_fee: Balance = fee
+			.saturating_sub(Self::usable_native_balance_for(who, reason))
+			.unique_saturated_into();
+
+		// release the locked native currency allowed to pay fee if it covers the gap,
+		// the release is never mixed with swap, so a failed swap leaves the locks intact
+		if !residual_fee.is_zero() && T::FeeLockAllowance::allowance(who) >= residual_fee {
+			T::FeeLockAllowance::release(who, residual_fee);
+			return Ok((None, 0));
+		}
+
+		// the account has opted out of auto swap
+		ensure!(!Self::no_auto_swap(who), Error::<T>::NotEnoughBalance);
+
+		// defer the non-native payers to later blocks to protect the DEX pricing
+		ensure!(
+			Self::swaps_this_block() < T::MaxFeeSwapsPerBlock::get(),
+			Error::<T>::FeeSwapLimitReached
+		);
+
+		// collect the candidates and their swap paths once, the currencies without
+		// balance are skipped before any DEX call
+		let candidates: Vec<(CurrencyId, Balance, Vec<Vec<CurrencyId>>)> = Self::fee_currency_order(who)
+			.into_iter()
+			.filter_map(|currency_id| {
+				let balance = <T as Trait>::Currency::free_balance(currency_id, who);
+				if balance.is_zero() {
+					None
+				} else {
+					Some((currency_id, balance, Self::fee_swap_paths_of(who, currency_id)))
+				}
+			})
+			.collect();
+
+		// iterator non-native currencies to get enough fee, only swap the gap
+		let mut currencies_tried: u32 = 0;
+		for (currency_id, balance, trading_paths) in candidates {
+			currencies_tried += 1;
+			let min_swap_amount = Self::min_swap_amount_of(currency_id);
+			let price_impact_limit = Some(Self::slippage_limit_of(currency_id));
+			// the supply amount is capped, move on to the next currency if it's not enough
+			let max_supply_amount = balance.min(T::MaxFeeSwapAmount::get());
+
+			// try routing through each stable currency until one succeeds
+			for trading_path in trading_paths {
+				// skip the micro swap which costs more than it's worth
+				if !min_swap_amount.is_zero()
+					&& T::DEX::get_swap_supply_amount(&trading_path, residual_fee, price_impact_limit)
+						.map_or(false, |supply_amount| supply_amount < min_swap_amount)
+				{
+					#[cfg(feature = "std")]
+					debug::debug!(
+						target: LOG_TARGET,
+						"skip fee swap of {:?} for {:?} with {:?} via {:?}: below min swap amount",
+						residual_fee,
+						who,
+						currency_id,
+						trading_path,
+					);
+					continue;
+				}
+
+				let result = T::DEX::swap_with_exact_target(
+					who,
+					&trading_path,
+					residual_fee,
+					max_supply_amount,
+					price_impact_limit,
+				);
+				#[cfg(feature = "std")]
+				debug::debug!(
+					target: LOG_TARGET,
+					"fee swap of {:?} for {:?} with {:?} via {:?}: {:?}",
+					residual_fee,
+					who,
+					currency_id,
+					trading_path,
+					result,
+				);
+				if let Ok(supply_amount) = result {
+					Self::deposit_event_at(
+						EventLevel::Verbose,
+						RawEvent::FeeSwapped(who.clone(), currency_id, supply_amount, residual_fee),
+					);
+					SwapsThisBlock::mutate(|count| *count = count.saturating_add(1));
+					// successfully swap, break iteration
+					return Ok((Some((currency_id, supply_amount, residual_fee)), currencies_tried));
+				}
+			}
+		}
+
+		// try to unreserve non-native currency to swap fee as the last resort
+		let swapped_from_reserved = Self::swap_fee_from_reserved(who, residual_fee);
+		#[cfg(feature = "std")]
+		debug::debug!(
+			target: LOG_TARGET,
+			"fee swap of {:?} for {:?} from reserved after {:?} currencies tried: {:?}",
+			residual_fee,
+			who,
+			currencies_tried,
+			swapped_from_reserved,
+		);
+		if !swapped_from_reserved {
+			#[cfg(feature = "std")]
+			debug::warn!(
+				target: LOG_TARGET,
+				"failed to swap fee of {:?} for {:?}",
+				residual_fee,
+				who,
+			);
+			return Err(Error::<T>::NotEnoughBalance.into());
+		}
+		SwapsThisBlock::mutate(|count| *count = count.saturating_add(1));
 
-	/// Event handler which calls when open account in system.
-	type OnCreatedAccount: Happened<Self::AccountId>;
+		Ok((None, currencies_tried))
+	}
 
-	/// Handler to kill account in system.
-	type KillAccount: Happened<Self::AccountId>;
+	/// Whether `call` of `who` pays no fee by default `fee`: the fee free
+	/// calls, the calls free by the fee policy, or under the free quota of
+	/// `who`, which is not used here.
+	fn pays_no_fee(who: &T::AccountId, call: &T::Call, fee: PalletBalanceOf<T>) -> bool {
+		call.using_encoded(Self::call_index_of)
+			.map_or(false, |call_index| Self::fee_free_calls(call_index))
+			|| T::CallFeePolicy::decide(call, fee) == FeeDecision::Free
+			|| (<Self as StoredMap<_, _>>::is_explicit(who)
+				&& Self::free_quota_used_in_era(who) < T::FreeQuotaPerEra::get())
+	}
 
-	/// Deposit for opening account, would be reserved until account closed.
-	type NewAccountDeposit: Get<Balance>;
+	/// Use the free quota of `who` in current era, return false if the quota
+	/// is used up or the account has not been opened.
+	fn use_free_quota(who: &T::AccountId) -> bool {
+		if !<Self as StoredMap<_, _>>::is_explicit(who) {
+			return false;
+		}
+		let used = Self::free_quota_used_in_era(who);
+		if used >= T::FreeQuotaPerEra::get() {
+			return false;
+		}
+		FreeQuotaUsed::<T>::insert(who, (T::EraProvider::get(), used.saturating_add(1)));
+		true
+	}
 
-	/// The treasury module account id to recycle assets.
-	type TreasuryModuleId: Get<ModuleId>;
+	/// The number of transactions of `who` free of fee in current era.
+	pub fn free_quota_used_in_era(who: &T::AccountId) -> u32 {
+		let (era, used) = Self::free_quota_used(who);
+		if era == T::EraProvider::get() {
+			used
+		} else {
+			Zero::zero()
+		}
+	}
 
-	/// The max slippage allowed when swap open account deposit or fee with DEX
-	type MaxSlippageSwapWithDEX: Get<Ratio>;
+	/// Whether `who` is never opened by swapping the received non-native currency.
+	fn is_excluded_from_auto_open(who: &T::AccountId) -> bool {
+		*who == Self::treasury_account_id() || T::ExcludedFromAutoOpen::get().contains(who)
+	}
 
-	/// Weight information for the extrinsics in this module.
-	type WeightInfo: WeightInfo;
-}
+	/// Accumulate the actual fee collected by currency, excluding the tip.
+	/// The swapped part is counted in the supply currency, and the rest is
+	/// counted in native currency. If the swapped native exceeds the fee, e.g.
+	/// it also paid the tip, the supply is counted pro rata.
+	fn record_fee_collected(fee: Balance, fee_swap: Option<(CurrencyId, Balance, Balance)>) {
+		let native_fee = match fee_swap {
+			Some((currency_id, supply_amount, native_amount)) => {
+				let swapped_fee = native_amount.min(fee);
+				let supply_for_fee = Ratio::checked_from_rational(swapped_fee, native_amount)
+					.map_or(supply_amount, |ratio| ratio.saturating_mul_int(supply_amount));
+				FeesCollectedByCurrency::mutate(currency_id, |total| *total = total.saturating_add(supply_for_fee));
+				fee.saturating_sub(swapped_fee)
+			}
+			None => fee,
+		};
+		if !native_fee.is_zero() {
+			FeesCollectedByCurrency::mutate(T::NativeCurrencyId::get(), |total| {
+				*total = total.saturating_add(native_fee)
+			});
+		}
+	}
 
-decl_error! {
-	/// Error for accounts manager module.
-	pub enum Error for Module<T: Trait> {
-		/// Balance is not sufficient
-		NotEnoughBalance,
-		/// Account ref count is not zero
-		NonZeroRefCount,
-		/// Account still has active reserved(include non-native token and native token beyond new account deposit)
-		StillHasActiveReserved,
+	/// The free native balance of `who` usable to pay transaction fee.
+	pub fn usable_native_balance(who: &T::AccountId) -> Balance {
+		Self::usable_native_balance_for(who, WithdrawReason::TransactionPayment.into()).unique_saturated_into()
 	}
-}
 
-decl_storage! {
-	trait Store for Module<T: Trait> as Accounts {}
-}
+	/// The free native balance of `who` which can be withdrawn for `reasons`,
+	/// that is the free balance above existential deposit and not frozen by
+	/// the locks for `reasons`.
+	///
+	/// `Currency` does not expose interface to get the frozen balance, so
+	/// search the max amount which passes `ensure_can_withdraw`. The search
+	/// is registered as extra weight by `WeightInfo::usable_native_balance`.
+	pub fn usable_native_balance_for(who: &T::AccountId, reasons: WithdrawReasons) -> PalletBalanceOf<T> {
+		let free_balance = <T as pallet_transaction_payment::Trait>::Currency::free_balance(who);
+		let can_withdraw = |amount: PalletBalanceOf<T>| {
+			<T as pallet_transaction_payment::Trait>::Currency::ensure_can_withdraw(
+				who,
+				amount,
+				reasons,
+				free_balance.saturating_sub(amount),
+			)
+			.is_ok()
+		};
 
-decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
-		type Error = Error<T>;
+		let mut low = Zero::zero();
+		let mut high =
+			free_balance.saturating_sub(<T as pallet_transaction_payment::Trait>::Currency::minimum_balance());
+		if can_withdraw(high) {
+			return high;
+		}
 
-		/// All non-native currency ids in Acala.
-		const AllNonNativeCurrencyIds: Vec<CurrencyId> = T::AllNonNativeCurrencyIds::get();
+		// the checks of the search are not covered by the weight of the caller
+		<system::Module<T>>::register_extra_weight_unchecked(
+			<T as Trait>::WeightInfo::usable_native_balance(),
+			DispatchClass::Normal,
+		);
+
+		// `low` is always withdrawable while `high` is not
+		let two: PalletBalanceOf<T> = 2u32.into();
+		while high.saturating_sub(low) > One::one() {
+			let mid = low + (high - low) / two;
+			if can_withdraw(mid) {
+				low = mid;
+			} else {
+				high = mid;
+			}
+		}
+		low
+	}
 
-		/// Native currency id, the actual received currency type as fee for treasury.
-		const NativeCurrencyId: CurrencyId = T::NativeCurrencyId::get();
+	/// Transfer all free `currency_id` of `who` to `recipient` if it is
+	/// allowed by `TransferGuard`.
+	fn guarded_transfer(currency_id: CurrencyId, who: &T::AccountId, recipient: &T::AccountId) -> DispatchResult {
+		let amount = <T as Trait>::Currency::free_balance(currency_id, who);
+		if !amount.is_zero() {
+			ensure!(
+				T::TransferGuard::allow(currency_id, who, recipient),
+				Error::<T>::TransferVetoed,
+			);
+		}
+		<T as Trait>::Currency::transfer(currency_id, who, recipient, amount)
+	}
 
-		/// Stable currency id.
-		const StableCurrencyId: CurrencyId = T::StableCurrencyId::get();
+	/// The deposit required to open account for `who`, which is
+	/// the deposit for opening account reduced by `OpenDepositDiscount`.
+	pub fn open_deposit_of(who: &T::AccountId) -> Balance {
+		let new_account_deposit = Self::new_account_deposit();
+		new_account_deposit.saturating_sub(T::OpenDepositDiscount::discount(who) * new_account_deposit)
+	}
 
-		/// Deposit for opening account, would be reserved until account closed.
-		const NewAccountDeposit: Balance = T::NewAccountDeposit::get();
+	/// The open account deposit reserved for opened account `who`.
+	fn reserved_open_deposit(who: &T::AccountId) -> Balance {
+		Self::open_deposits(who).unwrap_or_else(T::NewAccountDeposit::get)
+	}
 
-		/// The treasury module account id to recycle assets.
-		const TreasuryModuleId: ModuleId = T::TreasuryModuleId::get();
+	/// The currency in which the open account deposit is reserved for `who`.
+	fn deposit_currency_of(who: &T::AccountId) -> CurrencyId {
+		Self::deposit_currencies(who).unwrap_or_else(T::NativeCurrencyId::get)
+	}
 
-		/// The max slippage allowed when swap open account deposit or fee with DEX
-		const MaxSlippageSwapWithDEX: Ratio = T::MaxSlippageSwapWithDEX::get();
+	/// The open account deposit of `who` converted to non-native `DepositCurrencyId`
+	/// with DEX, None if `DepositCurrencyId` is native or can not be converted.
+	///
+	/// If DEX can not convert it, the deposit is `NonNativeDeposit` with the
+	/// same discount, so that the account can be opened without native
+	/// liquidity in DEX.
+	fn non_native_open_deposit_of(who: &T::AccountId) -> Option<(CurrencyId, Balance)> {
+		let deposit_currency_id = T::DepositCurrencyId::get();
+		let native_currency_id = T::NativeCurrencyId::get();
+		if deposit_currency_id == native_currency_id {
+			return None;
+		}
 
-		/// Kill self account from system.
-		///
-		/// The dispatch origin of this call must be Signed.
-		///
-		/// - `recipient`: the account as recipient to receive remaining currencies of the account will be killed,
-		///					None means no recipient is specified.
-		#[weight = <T as Trait>::WeightInfo::close_account(T::AllNonNativeCurrencyIds::get().len() as u32)]
-		pub fn close_account(origin, recipient: Option<T::AccountId>) {
-			with_transaction_result(|| {
-				let who = ensure_signed(origin)?;
+		let open_deposit = Self::open_deposit_of(who);
+		T::DEX::get_swap_supply_amount(
+			&Self::swap_path(deposit_currency_id, native_currency_id),
+			open_deposit,
+			Some(Self::slippage_limit_of(deposit_currency_id)),
+		)
+		.or_else(|| {
+			T::NonNativeDeposit::get()
+				.map(|deposit| deposit.saturating_sub(T::OpenDepositDiscount::discount(who) * deposit))
+		})
+		.map(|amount| (deposit_currency_id, amount))
+	}
 
-				// check must allow death,
-				// if native/non-native currencies has locks, means ref_count shouldn't be zero, can not close the account.
-				ensure!(
-					<system::Module<T>>::allow_death(&who),
-					Error::<T>::NonZeroRefCount,
-				);
+	/// Reserve the open account deposit in non-native `DepositCurrencyId` for
+	/// `who`, return true if succeeded.
+	fn reserve_non_native_open_deposit(who: &T::AccountId) -> bool {
+		if let Some((deposit_currency_id, deposit)) = Self::non_native_open_deposit_of(who) {
+			if <T as Trait>::Currency::reserve(deposit_currency_id, who, deposit).is_ok() {
+				OpenDeposits::<T>::insert(who, deposit);
+				DepositCurrencies::<T>::insert(who, deposit_currency_id);
+				return true;
+			}
+		}
+		false
+	}
 
-				let native_currency_id = T::NativeCurrencyId::get();
-				let new_account_deposit = T::NewAccountDeposit::get();
-				let total_reserved_native = <T as Trait>::Currency::reserved_balance(native_currency_id, &who);
+	/// Try to open account `who`, which is not opened yet or kept alive
+	/// without open account deposit.
+	fn do_try_open_account(who: &T::AccountId) -> DispatchResult {
+		let existed = <Self as StoredMap<_, _>>::is_explicit(who);
+		ensure!(
+			!existed || Self::open_deposits(who) == Some(Zero::zero()),
+			Error::<T>::AccountAlreadyOpened,
+		);
 
-				// ensure total reserved native is lte new account deposit,
-				// otherwise think the account still has active reserved kept by some bussiness.
-				ensure!(
-					new_account_deposit >= total_reserved_native,
-					Error::<T>::StillHasActiveReserved,
-				);
-				let treasury_account = Self::treasury_account_id();
-				let recipient = recipient.unwrap_or_else(|| treasury_account.clone());
+		let native_currency_id = T::NativeCurrencyId::get();
+		let open_deposit = Self::open_deposit_of(who);
+		let free = <T as Trait>::Currency::free_balance(native_currency_id, who);
+		if free < open_deposit {
+			// the swap opens the account which is not existed before
+			ensure!(
+				Self::swap_for_native(who, None, open_deposit - free).is_some(),
+				Error::<T>::CannotOpenAccount,
+			);
+		}
+
+		if existed {
+			<T as Trait>::Currency::reserve(native_currency_id, who, open_deposit)
+				.map_err(|_| Error::<T>::CannotOpenAccount)?;
+			OpenDeposits::<T>::insert(who, open_deposit);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::AccountOpened(who.clone()));
+		} else {
+			ensure!(
+				<Self as StoredMap<_, _>>::is_explicit(who),
+				Error::<T>::CannotOpenAccount,
+			);
+		}
 
-				// unreserve all reserved native currency
-				<T as Trait>::Currency::unreserve(native_currency_id, &who, total_reserved_native);
+		Ok(())
+	}
 
-				// transfer all free to recipient
-				<T as Trait>::Currency::transfer(native_currency_id, &who, &recipient, <T as Trait>::Currency::free_balance(native_currency_id, &who))?;
+	/// Swap `amount` native currency for `who` with the non-native currencies
+	/// it holds to fund the open account deposit, `preferred` is tried first.
+	/// Return the swapped currency if any swap succeeded.
+	fn swap_for_native(who: &T::AccountId, preferred: Option<CurrencyId>, amount: Balance) -> Option<CurrencyId> {
+		let mut currency_ids: Vec<CurrencyId> = preferred.into_iter().collect();
+		currency_ids.extend(
+			T::AllNonNativeCurrencyIds::get()
+				.into_iter()
+				.filter(|id| Some(*id) != preferred),
+		);
+
+		for currency_id in currency_ids {
+			let balance = <T as Trait>::Currency::free_balance(currency_id, who);
+			if balance.is_zero() {
+				continue;
+			}
 
-				// handle other non-native currencies
-				for currency_id in T::AllNonNativeCurrencyIds::get() {
-					// ensure the account has no active reserved of non-native token
-					ensure!(
-						<T as Trait>::Currency::reserved_balance(currency_id, &who).is_zero(),
-						Error::<T>::StillHasActiveReserved,
+			let price_impact_limit = Some(Self::slippage_limit_of(currency_id));
+			for trading_path in Self::fee_swap_paths(currency_id) {
+				if let Ok(supply_amount) =
+					T::DEX::swap_with_exact_target(who, &trading_path, amount, balance, price_impact_limit)
+				{
+					Self::deposit_event_at(
+						EventLevel::Normal,
+						RawEvent::DepositFundedViaSwap(who.clone(), currency_id, supply_amount, amount),
 					);
-
-					// transfer all free to recipient
-					<T as Trait>::Currency::transfer(currency_id, &who, &recipient, <T as Trait>::Currency::free_balance(currency_id, &who))?;
+					return Some(currency_id);
 				}
-
-				// finally kill the account
-				T::KillAccount::happened(&who);
-
-				Ok(())
-			})?;
+			}
 		}
-	}
-}
 
-impl<T: Trait> Module<T> {
-	/// Get treasury account id.
-	pub fn treasury_account_id() -> T::AccountId {
-		T::TreasuryModuleId::get().into_account()
+		None
 	}
 
-	/// Open account by reserve native token.
+	/// Open account by reserve `DepositCurrencyId`, or native token if failed.
 	///
-	/// If not enough free balance to reserve, all the balance would be
-	/// transferred to treasury instead.
+	/// If not enough free balance to reserve, the account is kept alive
+	/// without deposit when its free balance is not below `DustThreshold`,
+	/// otherwise all the balance would be transferred to treasury instead.
 	fn open_account(k: &T::AccountId) {
+		LastOpenAttempt::<T>::remove(k);
+
 		let native_currency_id = T::NativeCurrencyId::get();
-		if <T as Trait>::Currency::reserve(native_currency_id, k, T::NewAccountDeposit::get()).is_ok() {
+		let open_deposit = Self::open_deposit_of(k);
+		if Self::reserve_non_native_open_deposit(k) {
+			T::OnCreatedAccount::happened(&k);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::AccountOpened(k.clone()));
+		} else if <T as Trait>::Currency::reserve(native_currency_id, k, open_deposit).is_ok() {
+			OpenDeposits::<T>::insert(k, open_deposit);
 			T::OnCreatedAccount::happened(&k);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::AccountOpened(k.clone()));
+		} else if <T as Trait>::Currency::free_balance(native_currency_id, k) >= T::DustThreshold::get() {
+			OpenDeposits::<T>::insert(k, Balance::zero());
+			T::OnCreatedAccount::happened(&k);
+			Self::deposit_event_at(EventLevel::Normal, RawEvent::AccountOpened(k.clone()));
 		} else {
 			let treasury_account = Self::treasury_account_id();
 
@@ -211,58 +2663,220 @@ impl<T: Trait> Module<T> {
 			// account deposit best practice is to ensure that the first transfer received
 			// by treasury account is sufficient to open an account.
 			if *k != treasury_account {
-				// send dust native currency to treasury account.
+				// burn `DustBurnRatio` of dust native currency and send the remainder to
+				// treasury account.
 				// transfer all free balances from a new account to treasury account, so it
 				// shouldn't fail. but even it failed, leave some dust storage is not a critical
 				// issue, just open account without reserve NewAccountDeposit.
-				if <T as Trait>::Currency::transfer(
-					native_currency_id,
-					k,
-					&treasury_account,
-					<T as Trait>::Currency::free_balance(native_currency_id, k),
-				)
+				let dust = <T as Trait>::Currency::free_balance(native_currency_id, k);
+				let burned = T::DustBurnRatio::get().saturating_mul_int(dust).min(dust);
+				let swept = dust.saturating_sub(burned);
+				if with_transaction_result(|| {
+					<T as Trait>::Currency::withdraw(native_currency_id, k, burned)?;
+					<T as Trait>::Currency::transfer(native_currency_id, k, &treasury_account, swept)
+				})
 				.is_ok()
 				{
 					// remove the account info pretend that opening account has never happened
 					system::Account::<T>::remove(k);
+					T::OnKillAccount::happened(&(k.clone(), KillReason::DustSwept));
+
+					SweptDust::mutate(|total| *total = total.saturating_add(swept));
+					TotalSweptDust::mutate(|total| *total = total.saturating_add(swept));
+					Self::deposit_event_at(EventLevel::Normal, RawEvent::DustSwept(k.clone(), swept));
+					if !burned.is_zero() {
+						Self::deposit_event_at(EventLevel::Normal, RawEvent::DustBurned(k.clone(), burned));
+					}
 				}
 			}
 		}
 	}
 }
 
+impl<T: Trait> Module<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + Encode,
+	PalletBalanceOf<T>: FixedPointOperand,
+{
+	/// The native fee of a transaction with `len` and `info`, include the
+	/// fee of the extra weight of swapping with DEX if `with_swap`.
+	pub fn query_native_fee(len: u32, info: &DispatchInfo, with_swap: bool) -> Balance {
+		let fee: Balance =
+			<pallet_transaction_payment::Module<T>>::compute_fee(len, info, Zero::zero()).unique_saturated_into();
+		if with_swap {
+			let swap_fee: Balance =
+				<T as pallet_transaction_payment::Trait>::WeightToFee::calc(&<T as Trait>::WeightInfo::swap_fee())
+					.unique_saturated_into();
+			fee.saturating_add(swap_fee)
+		} else {
+			fee
+		}
+	}
+
+	/// The amount of `currency_id` required to pay the fee of a transaction
+	/// with `len` and `info`. If `currency_id` is not native currency, the
+	/// fee is paid by swapping with DEX, and the extra fee of the swap is
+	/// included. Return None if can not swap under the slippage limit.
+	pub fn query_fee_in_currency(len: u32, info: &DispatchInfo, currency_id: CurrencyId) -> Option<Balance> {
+		if currency_id == T::NativeCurrencyId::get() {
+			return Some(Self::query_native_fee(len, info, false));
+		}
+
+		T::DEX::get_swap_supply_amount(
+			&Self::fee_swap_path(currency_id),
+			Self::query_native_fee(len, info, true),
+			Some(Self::slippage_limit_of(currency_id)),
+		)
+	}
+
+	/// Simulate charging the fee of `call` from `who` without changing any
+	/// state, the swap with DEX is only queried. Return the native fee and
+	/// the currency which would pay it, the currency is None if no fee is
+	/// charged or no currency can afford the fee.
+	pub fn simulate_fee(
+		who: &T::AccountId,
+		call: &T::Call,
+		info: &DispatchInfo,
+		len: u32,
+	) -> (Balance, Option<CurrencyId>) {
+		if call
+			.using_encoded(Self::call_index_of)
+			.map_or(false, |call_index| Self::fee_free_calls(call_index))
+		{
+			return (Zero::zero(), None);
+		}
+
+		let fee = <pallet_transaction_payment::Module<T>>::compute_fee(len, info, Zero::zero());
+		let fee = Self::call_fee_multiplier_of(call).saturating_mul_int(fee);
+		let fee: Balance = match T::CallFeePolicy::decide(call, fee) {
+			FeeDecision::Charge(amount) => amount.unique_saturated_into(),
+			FeeDecision::Free => return (Zero::zero(), None),
+			FeeDecision::Reject => return (fee.unique_saturated_into(), None),
+		};
+
+		if <Self as StoredMap<_, _>>::is_explicit(who) && Self::free_quota_used_in_era(who) < T::FreeQuotaPerEra::get()
+		{
+			return (Zero::zero(), None);
+		}
+
+		let usable_native: Balance =
+			Self::usable_native_balance_for(who, WithdrawReason::TransactionPayment.into()).unique_saturated_into();
+		if fee <= usable_native {
+			return (fee, Some(T::NativeCurrencyId::get()));
+		}
+		if Self::no_auto_swap(who) {
+			return (fee, None);
+		}
+
+		let residual_fee = fee.saturating_sub(usable_native);
+		let fee_currency_id = Self::fee_currency_order(who)
+			.into_iter()
+			.find(|currency_id| Self::can_swap_fee(who, *currency_id, residual_fee));
+		(fee, fee_currency_id)
+	}
+
+	/// The currencies which `who` can pay the whole `fee` with right now,
+	/// the non-native currencies are swapped to native currency with DEX.
+	pub fn payable_fee_currencies(who: &T::AccountId, fee: Balance) -> Vec<CurrencyId> {
+		let mut currency_ids = vec![];
+		let usable_native: Balance =
+			Self::usable_native_balance_for(who, WithdrawReason::TransactionPayment.into()).unique_saturated_into();
+		if fee <= usable_native {
+			currency_ids.push(T::NativeCurrencyId::get());
+		}
+
+		currency_ids.extend(
+			T::AllNonNativeCurrencyIds::get()
+				.into_iter()
+				.filter(|currency_id| Self::can_swap_fee(who, *currency_id, fee)),
+		);
+		currency_ids
+	}
+
+	/// Whether `who` can swap `currency_id` to `native_amount` native currency
+	/// to pay fee, within the slippage limit and the supply limits.
+	fn can_swap_fee(who: &T::AccountId, currency_id: CurrencyId, native_amount: Balance) -> bool {
+		Self::quote_fee_swap(who, currency_id, native_amount).is_some()
+	}
+
+	/// The amount of `currency_id` consumed to swap `native_fee` native
+	/// currency to pay fee for `who`, along the first of the fee swap paths
+	/// which satisfies it. None if no path can satisfy it within the slippage
+	/// limit and the supply limits.
+	///
+	/// Only queries the DEX, no state is mutated.
+	pub fn quote_fee_swap(who: &T::AccountId, currency_id: CurrencyId, native_fee: Balance) -> Option<Balance> {
+		let max_supply_amount = <T as Trait>::Currency::free_balance(currency_id, who).min(T::MaxFeeSwapAmount::get());
+		let min_swap_amount = Self::min_swap_amount_of(currency_id);
+		let price_impact_limit = Some(Self::slippage_limit_of(currency_id));
+
+		Self::fee_swap_paths_of(who, currency_id)
+			.into_iter()
+			.find_map(|trading_path| {
+				T::DEX::get_swap_supply_amount(&trading_path, native_fee, price_impact_limit)
+					.filter(|supply_amount| *supply_amount >= min_swap_amount && *supply_amount <= max_supply_amount)
+			})
+	}
+}
+
 /// Note: Currently `pallet_balances` does not implement `OnReceived`,
 /// which means here only do the preparations for opening an account by
 /// non-native currency, actual process of opening account is handled by
 /// `StoredMap`.
+///
+/// The swap with DEX may call back into `on_received` for the same account,
+/// which must not swap again, so the account is flagged in `SwappingToOpen`
+/// during the swap.
 impl<T: Trait> OnReceived<T::AccountId, CurrencyId, Balance> for Module<T> {
 	fn on_received(who: &T::AccountId, currency_id: CurrencyId, _: Balance) {
 		let native_currency_id = T::NativeCurrencyId::get();
 
-		if !<Self as StoredMap<_, _>>::is_explicit(who) && currency_id != native_currency_id {
-			let stable_currency_id = T::StableCurrencyId::get();
-			let trading_path = if currency_id == stable_currency_id {
-				vec![stable_currency_id, native_currency_id]
-			} else {
-				vec![currency_id, stable_currency_id, native_currency_id]
-			};
+		if !<Self as StoredMap<_, _>>::is_explicit(who)
+			&& currency_id != native_currency_id
+			&& !Self::swapping_to_open(who)
+			&& !Self::is_excluded_from_auto_open(who)
+		{
+			// open account with the received non-native deposit currency directly, no need to swap
+			if let Some((deposit_currency_id, deposit)) = Self::non_native_open_deposit_of(who) {
+				if <T as Trait>::Currency::free_balance(deposit_currency_id, who) >= deposit {
+					system::Account::<T>::mutate(who, |_| {});
+					Self::open_account(who);
+					return;
+				}
+			}
 
+			// Try the received currency first, then other non-native currencies the account holds.
 			// Successful swap will cause changes in native currency,
 			// which also means that it will open a new account
 			// exchange token to native currency and open account.
-			// If swap failed, will leave some dust storage is not a critical issue,
+			// If all swaps failed, will leave some dust storage is not a critical issue,
 			// just open account without reserve NewAccountDeposit.
 			// Don't recycle non-native to avoid unreasonable loss
 			// due to insufficient liquidity of DEX, can try to open this
 			// account again later. If want to recycle dust non-native,
 			// should handle by the currencies module.
-			let _ = T::DEX::swap_with_exact_target(
-				who,
-				&trading_path,
-				T::NewAccountDeposit::get(),
-				<T as Trait>::Currency::free_balance(currency_id, who),
-				Some(T::MaxSlippageSwapWithDEX::get()),
-			);
+			// Skip the swap within `OpenAttemptCooldown` after a failed attempt, to bound the
+			// wasted work of repeated receipts of illiquid currency.
+			let now = <system::Module<T>>::block_number();
+			if let Some(last_attempt) = Self::last_open_attempt(who) {
+				if now < last_attempt.saturating_add(T::OpenAttemptCooldown::get()) {
+					return;
+				}
+			}
+
+			SwappingToOpen::<T>::insert(who, true);
+			let swapped_currency_id = Self::swap_for_native(who, Some(currency_id), Self::open_deposit_of(who));
+			SwappingToOpen::<T>::remove(who);
+
+			match swapped_currency_id {
+				Some(swapped_currency_id) => T::OnAccountOpenedViaSwap::happened(&(who.clone(), swapped_currency_id)),
+				None => {
+					LastOpenAttempt::<T>::insert(who, now);
+					OpenAttemptExpiries::<T>::mutate(now.saturating_add(T::OpenAttemptCooldown::get()), |accounts| {
+						accounts.push(who.clone())
+					});
+				}
+			}
 		}
 	}
 }
@@ -289,6 +2903,7 @@ impl<T: Trait> StoredMap<T::AccountId, T::AccountData> for Module<T> {
 
 	fn remove(k: &T::AccountId) {
 		T::KillAccount::happened(k);
+		T::OnKillAccount::happened(&(k.clone(), KillReason::Reaped));
 	}
 
 	fn mutate<R>(k: &T::AccountId, f: impl FnOnce(&mut T::AccountData) -> R) -> R {
@@ -346,18 +2961,60 @@ pub fn split_inner<T, R, S>(option: Option<T>, splitter: impl FnOnce(T) -> (R, S
 }
 
 impl<T: Trait> OnKilledAccount<T::AccountId> for Module<T> {
-	fn on_killed_account(_who: &T::AccountId) {}
+	fn on_killed_account(who: &T::AccountId) {
+		OpenDeposits::<T>::remove(who);
+		DepositCurrencies::<T>::remove(who);
+		ConsolidationOwner::<T>::remove(who);
+		FeeCurrency::<T>::remove(who);
+		FeeCurrencyChangedAt::<T>::remove(who);
+		CachedFeePath::<T>::remove_prefix(who);
+		Referrer::<T>::remove(who);
+		FeePayerApprovals::<T>::remove_prefix(who);
+		MultisigFeePayers::<T>::remove(who);
+		NoAutoSwap::<T>::remove(who);
+		FreeQuotaUsed::<T>::remove(who);
+		LastOpenAttempt::<T>::remove(who);
+		CloseRequestDeposits::<T>::remove(who);
+
+		let max_recently_killed = T::MaxRecentlyKilled::get() as usize;
+		if max_recently_killed > 0 {
+			RecentlyKilled::<T>::mutate(|recently_killed| {
+				if recently_killed.len() >= max_recently_killed {
+					let evicted = recently_killed.len() + 1 - max_recently_killed;
+					recently_killed.drain(..evicted);
+				}
+				recently_killed.push((who.clone(), <system::Module<T>>::block_number()));
+			});
+		}
+	}
 }
 
 /// Require the transactor pay for themselves and maybe include a tip to gain
-/// additional priority in the queue.
+/// additional priority in the queue. The fee can be paid by another account
+/// which has approved to pay for the transactor, and the tip can be paid by
+/// non-native currency which is swapped to native by DEX. The transaction is
+/// rejected if the fee exceeds the optional max fee. The tip can also be
+/// derived from the base fee by a priority multiplier.
+///
+/// It must be placed before `CheckWeight` in `SignedExtra`, so that the weight
+/// of fee swaps is checked against the block weight limit.
 #[derive(Encode, Decode, Clone, Eq, PartialEq)]
-pub struct ChargeTransactionPayment<T: Trait + Send + Sync>(#[codec(compact)] PalletBalanceOf<T>);
+pub struct ChargeTransactionPayment<T: Trait + Send + Sync>(
+	#[codec(compact)] PalletBalanceOf<T>,
+	Option<T::AccountId>,
+	Option<(CurrencyId, Balance)>,
+	Option<PalletBalanceOf<T>>,
+	Option<Ratio>,
+);
 
 impl<T: Trait + Send + Sync> sp_std::fmt::Debug for ChargeTransactionPayment<T> {
 	#[cfg(feature = "std")]
 	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
-		write!(f, "ChargeTransactionPayment<{:?}>", self.0)
+		write!(
+			f,
+			"ChargeTransactionPayment<{:?}, {:?}, {:?}, {:?}, {:?}>",
+			self.0, self.1, self.2, self.3, self.4
+		)
 	}
 	#[cfg(not(feature = "std"))]
 	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
@@ -367,24 +3024,181 @@ impl<T: Trait + Send + Sync> sp_std::fmt::Debug for ChargeTransactionPayment<T>
 
 impl<T: Trait + Send + Sync> ChargeTransactionPayment<T>
 where
-	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + IsSubType<orml_currencies::Call<T>>,
+	T::Call:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + IsSubType<orml_currencies::Call<T>> + Encode,
 	PalletBalanceOf<T>: Send + Sync + FixedPointOperand,
 {
 	/// utility constructor. Used only in client/factory code.
 	pub fn from(fee: PalletBalanceOf<T>) -> Self {
-		Self(fee)
+		Self(fee, None, None, None, None)
 	}
 
-	fn withdraw_fee(
+	/// utility constructor with the fee payer. Used only in client/factory
+	/// code.
+	pub fn with_fee_payer(fee: PalletBalanceOf<T>, fee_payer: T::AccountId) -> Self {
+		Self(fee, Some(fee_payer), None, None, None)
+	}
+
+	/// utility constructor with the non-native tip. Used only in
+	/// client/factory code.
+	pub fn with_non_native_tip(fee: PalletBalanceOf<T>, currency_id: CurrencyId, tip: Balance) -> Self {
+		Self(fee, None, Some((currency_id, tip)), None, None)
+	}
+
+	/// utility constructor with the max fee in native currency. Used only in
+	/// client/factory code.
+	pub fn with_max_fee(fee: PalletBalanceOf<T>, max_fee: PalletBalanceOf<T>) -> Self {
+		Self(fee, None, None, Some(max_fee), None)
+	}
+
+	/// utility constructor with the priority multiplier, the tip is the base
+	/// fee times `multiplier - 1`. Used only in client/factory code.
+	pub fn from_priority_multiplier(multiplier: Ratio) -> Self {
+		Self(Zero::zero(), None, None, None, Some(multiplier))
+	}
+
+	/// The native tip of the transaction.
+	pub fn tip(&self) -> PalletBalanceOf<T> {
+		self.0
+	}
+
+	/// The account requested to pay the fee for the transactor.
+	pub fn requested_fee_payer(&self) -> Option<&T::AccountId> {
+		self.1.as_ref()
+	}
+
+	/// The non-native currency and amount of the tip, which is swapped to
+	/// native currency by DEX.
+	pub fn non_native_tip(&self) -> Option<(CurrencyId, Balance)> {
+		self.2
+	}
+
+	/// The max fee in native currency the transactor is willing to pay, None
+	/// means unlimited.
+	pub fn max_fee(&self) -> Option<PalletBalanceOf<T>> {
+		self.3
+	}
+
+	/// The priority multiplier of the base fee, from which the tip is derived.
+	pub fn priority_multiplier(&self) -> Option<Ratio> {
+		self.4
+	}
+
+	/// The tip derived from the base fee by the priority multiplier, zero if
+	/// the multiplier is not set or not above one.
+	fn multiplier_tip(&self, len: usize, info: &DispatchInfoOf<T::Call>) -> PalletBalanceOf<T> {
+		self.4.map_or_else(Zero::zero, |multiplier| {
+			let base_fee = <pallet_transaction_payment::Module<T>>::compute_fee(len as u32, info, Zero::zero());
+			multiplier.saturating_sub(Ratio::one()).saturating_mul_int(base_fee)
+		})
+	}
+
+	/// The total native tip paid by `who`, the non-native tip is swapped to
+	/// native currency by DEX.
+	fn native_tip(&self, who: &T::AccountId) -> Result<PalletBalanceOf<T>, TransactionValidityError> {
+		match self.2 {
+			Some((currency_id, amount)) if !amount.is_zero() => {
+				let native_amount = T::DEX::swap_with_exact_supply(
+					who,
+					&Module::<T>::fee_swap_path(currency_id),
+					amount,
+					Zero::zero(),
+					Some(Module::<T>::slippage_limit_of(currency_id)),
+				)
+				.map_err(|_| TransactionValidityError::from(InvalidTransaction::Payment))?;
+				Ok(self.0.saturating_add(native_amount.unique_saturated_into()))
+			}
+			_ => Ok(self.0),
+		}
+	}
+
+	/// The total native tip of the transaction, including the tip derived by
+	/// the priority multiplier. The non-native tip is not swapped if the
+	/// transaction pays no fee.
+	fn total_tip(
 		&self,
 		who: &T::AccountId,
-		_call: &T::Call,
+		call: &T::Call,
+		info: &DispatchInfoOf<T::Call>,
+		len: usize,
+	) -> Result<PalletBalanceOf<T>, TransactionValidityError> {
+		let tip = self.0.saturating_add(self.multiplier_tip(len, info));
+		let base_fee = <pallet_transaction_payment::Module<T>>::compute_fee(len as u32, info, Zero::zero());
+		let default_fee = Module::<T>::call_fee_multiplier_of(call)
+			.saturating_mul_int(base_fee)
+			.saturating_add(tip);
+		if Module::<T>::pays_no_fee(who, call, default_fee) {
+			return Ok(tip);
+		}
+		Ok(self.native_tip(who)?.saturating_add(self.multiplier_tip(len, info)))
+	}
+
+	/// The account to pay fee for `who`, the fee payer must have approved
+	/// to pay for `who`. If no fee payer is requested and `call` is a
+	/// multisig approval, the payer registered by the multisig pays if it
+	/// still approves to.
+	fn fee_payer(&self, who: &T::AccountId, call: &T::Call) -> Result<T::AccountId, TransactionValidityError> {
+		match &self.1 {
+			Some(fee_payer) if Module::<T>::fee_payer_approvals(fee_payer, who) => Ok(fee_payer.clone()),
+			Some(_) => Err(InvalidTransaction::BadProof.into()),
+			None => Ok(T::MultisigApproval::multisig_of(who, call)
+				.and_then(|multisig| {
+					Module::<T>::multisig_fee_payer(&multisig)
+						.filter(|payer| Module::<T>::fee_payer_approvals(payer, &multisig))
+				})
+				.unwrap_or_else(|| who.clone())),
+		}
+	}
+
+	fn withdraw_fee(
+		who: &T::AccountId,
+		call: &T::Call,
 		info: &DispatchInfoOf<T::Call>,
 		len: usize,
-	) -> Result<(PalletBalanceOf<T>, Option<NegativeImbalanceOf<T>>), TransactionValidityError> {
+		tip: PalletBalanceOf<T>,
+		max_fee: Option<PalletBalanceOf<T>>,
+	) -> Result<
+		(
+			PalletBalanceOf<T>,
+			Option<NegativeImbalanceOf<T>>,
+			Option<(CurrencyId, Balance, Balance)>,
+			u32,
+			(Ratio, Ratio),
+		),
+		TransactionValidityError,
+	> {
+		let no_fee = (Zero::zero(), None, None, 0, (Ratio::one(), Ratio::one()));
+
 		// pay any fees.
-		let tip = self.0;
-		let fee = <pallet_transaction_payment::Module<T>>::compute_fee(len as u32, info, tip);
+		// the fee free calls pay nothing
+		if call
+			.using_encoded(Module::<T>::call_index_of)
+			.map_or(false, |call_index| Module::<T>::fee_free_calls(call_index))
+		{
+			return Ok(no_fee);
+		}
+
+		// the base fee is multiplied for the congestion-sensitive calls, but not the tip
+		let base_fee = <pallet_transaction_payment::Module<T>>::compute_fee(len as u32, info, Zero::zero());
+		let multiplier = Module::<T>::call_fee_multiplier_of(call);
+		let default_fee = multiplier.saturating_mul_int(base_fee).saturating_add(tip);
+		let fee = match T::CallFeePolicy::decide(call, default_fee) {
+			FeeDecision::Charge(amount) => amount,
+			FeeDecision::Free => return Ok(no_fee),
+			FeeDecision::Reject => return Err(InvalidTransaction::Call.into()),
+		};
+		// the adjustment of the fee policy, which is applied to the actual fee on refund
+		let policy_ratio = Ratio::checked_from_rational(fee, default_fee).unwrap_or_else(Ratio::one);
+
+		// the transactions under the free quota of the era pay nothing
+		if Module::<T>::use_free_quota(who) {
+			return Ok(no_fee);
+		}
+
+		// reject before any swap if the fee(including tip) exceeds the max fee
+		if max_fee.map_or(false, |max_fee| fee > max_fee) {
+			return Err(InvalidTransaction::Payment.into());
+		}
 
 		let reason = if tip.is_zero() {
 			WithdrawReason::TransactionPayment.into()
@@ -392,63 +3206,64 @@ where
 			WithdrawReason::TransactionPayment | WithdrawReason::Tip
 		};
 
-		// check native balance if is enough
-		let native_is_enough = <T as pallet_transaction_payment::Trait>::Currency::free_balance(who)
-			.checked_sub(&fee)
-			.map_or(false, |new_free_balance| {
-				<T as pallet_transaction_payment::Trait>::Currency::ensure_can_withdraw(
-					who,
-					fee,
-					reason,
-					new_free_balance,
-				)
-				.is_ok()
-			});
-
-		// try to use non-native currency to swap native currency by exchange with DEX
-		if !native_is_enough {
-			let native_currency_id = T::NativeCurrencyId::get();
-			let stable_currency_id = T::StableCurrencyId::get();
-			let other_currency_ids = T::AllNonNativeCurrencyIds::get();
-			let price_impact_limit = Some(T::MaxSlippageSwapWithDEX::get());
-			// Note: in fact, just obtain the gap between of fee and usable native currency
-			// amount, but `Currency` does not expose interface to get usable balance by
-			// specific reason. Here try to swap the whole fee by non-native currency.
-			let balance_fee: Balance = fee.unique_saturated_into();
-
-			// iterator non-native currencies to get enough fee
-			for currency_id in other_currency_ids {
-				let trading_path = if currency_id == stable_currency_id {
-					vec![stable_currency_id, native_currency_id]
-				} else {
-					vec![currency_id, stable_currency_id, native_currency_id]
-				};
-
-				if T::DEX::swap_with_exact_target(
-					who,
-					&trading_path,
-					balance_fee,
-					<T as Trait>::Currency::free_balance(currency_id, who),
-					price_impact_limit,
-				)
-				.is_ok()
+		// swap and withdraw atomically, the swap is reverted if the fee can not be withdrawn
+		with_transaction_result(|| {
+			let (fee_swap, currencies_tried) = match Module::<T>::do_ensure_can_charge_fee(who, fee, reason) {
+				Ok(result) => result,
+				// the fee relief fund covers the shortfall of relief calls as the last resort
+				Err(_)
+					if call
+						.using_encoded(Module::<T>::call_index_of)
+						.map_or(false, |call_index| Module::<T>::fee_relief_calls(call_index))
+						&& Module::<T>::relieve_fee(who, fee, reason) =>
 				{
-					// successfully swap, break iteration
-					break;
+					(None, T::AllNonNativeCurrencyIds::get().len() as u32)
 				}
-			}
-		}
+				Err(e) => return Err(e),
+			};
 
-		// withdraw native currency as fee
-		match <T as pallet_transaction_payment::Trait>::Currency::withdraw(
-			who,
-			fee,
-			reason,
-			ExistenceRequirement::KeepAlive,
-		) {
-			Ok(imbalance) => Ok((fee, Some(imbalance))),
-			Err(_) => Err(InvalidTransaction::Payment.into()),
-		}
+			match <T as pallet_transaction_payment::Trait>::Currency::withdraw(
+				who,
+				fee,
+				reason,
+				Module::<T>::fee_existence_requirement(call),
+			) {
+				Ok(imbalance) => {
+					// the fee relief debt is repaid once the account has native currency again
+					Module::<T>::repay_fee_relief_debt(who);
+					Ok(Ok((
+						fee,
+						Some(imbalance),
+						fee_swap,
+						currencies_tried,
+						(multiplier, policy_ratio),
+					)))
+				}
+				// the swap succeeded, but native currency is still not withdrawable
+				Err(_e) if fee_swap.is_some() => {
+					#[cfg(feature = "std")]
+					debug::warn!(
+						target: LOG_TARGET,
+						"fee of {:?} is swapped but withdraw failed for {:?}: {:?}",
+						fee,
+						who,
+						_e,
+					);
+					Err(Error::<T>::FeeWithdrawBlocked.into())
+				}
+				Err(e) => Err(e),
+			}
+		})
+		.and_then(|result| result)
+		.map_err(|e| {
+			if e == DispatchError::from(Error::<T>::FeeWithdrawBlocked) {
+				InvalidTransaction::Custom(FEE_WITHDRAW_BLOCKED).into()
+			} else if e == DispatchError::from(Error::<T>::NotEnoughBalance) {
+				InvalidTransaction::Custom(FEE_SWAP_FAILED).into()
+			} else {
+				InvalidTransaction::Payment.into()
+			}
+		})
 	}
 
 	/// Get an appropriate priority for a transaction with the given length and
@@ -463,14 +3278,24 @@ where
 	/// `(1/1)`, its priority is `fee * min(1, 4) = fee * 1`. This means
 	///  that the transaction which consumes more resources (either length or
 	/// weight) with the same `fee` ends up having lower priority.
+	///
+	/// The coefficient is computed in fixed-point, so that small differences
+	/// in resource consumption still result in different priorities, and it's
+	/// saturated to `TransactionPriority` only at the end.
+	///
+	/// Transactions which pay zero fee get `ZeroFeePriority`.
 	fn get_priority(len: usize, info: &DispatchInfoOf<T::Call>, final_fee: PalletBalanceOf<T>) -> TransactionPriority {
-		let weight_saturation = T::MaximumBlockWeight::get() / info.weight.max(1);
-		let len_saturation = T::MaximumBlockLength::get() as u64 / (len as u64).max(1);
-		let coefficient: PalletBalanceOf<T> = weight_saturation
-			.min(len_saturation)
-			.saturated_into::<PalletBalanceOf<T>>();
-		final_fee
-			.saturating_mul(coefficient)
+		if final_fee.is_zero() {
+			return T::ZeroFeePriority::get();
+		}
+
+		let weight_saturation = Ratio::checked_from_rational(T::MaximumBlockWeight::get(), info.weight.max(1))
+			.unwrap_or_else(Ratio::max_value);
+		let len_saturation = Ratio::checked_from_rational(T::MaximumBlockLength::get(), (len as u64).max(1))
+			.unwrap_or_else(Ratio::max_value);
+		let coefficient = weight_saturation.min(len_saturation);
+		coefficient
+			.saturating_mul_int(final_fee.saturated_into::<Balance>())
 			.saturated_into::<TransactionPriority>()
 	}
 }
@@ -478,7 +3303,8 @@ where
 impl<T: Trait + Send + Sync> SignedExtension for ChargeTransactionPayment<T>
 where
 	PalletBalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
-	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + IsSubType<orml_currencies::Call<T>>,
+	T::Call:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + IsSubType<orml_currencies::Call<T>> + Encode,
 {
 	const IDENTIFIER: &'static str = "ChargeTransactionPayment";
 	type AccountId = T::AccountId;
@@ -489,6 +3315,9 @@ where
 		Self::AccountId,
 		Option<NegativeImbalanceOf<T>>,
 		PalletBalanceOf<T>,
+		Option<(CurrencyId, Balance, Balance)>,
+		(u8, u8),
+		(Ratio, Ratio),
 	);
 
 	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> {
@@ -502,7 +3331,9 @@ where
 		info: &DispatchInfoOf<Self::Call>,
 		len: usize,
 	) -> TransactionValidity {
-		let (fee, _) = self.withdraw_fee(who, call, info, len)?;
+		let fee_payer = self.fee_payer(who, call)?;
+		let tip = self.total_tip(&fee_payer, call, info, len)?;
+		let (fee, _, _, _, _) = Self::withdraw_fee(&fee_payer, call, info, len, tip, self.3)?;
 		Ok(ValidTransaction {
 			priority: Self::get_priority(len, info, fee),
 			..Default::default()
@@ -516,8 +3347,23 @@ where
 		info: &DispatchInfoOf<Self::Call>,
 		len: usize,
 	) -> Result<Self::Pre, TransactionValidityError> {
-		let (fee, imbalance) = self.withdraw_fee(who, call, info, len)?;
-		Ok((self.0, who.clone(), imbalance, fee))
+		let fee_payer = self.fee_payer(who, call)?;
+		let tip = self.total_tip(&fee_payer, call, info, len)?;
+		let (fee, imbalance, fee_swap, currencies_tried, fee_ratios) =
+			Self::withdraw_fee(&fee_payer, call, info, len, tip, self.3)?;
+
+		// the work of swapping currencies to pay fee is not covered by the weight of the call,
+		// it's registered before `CheckWeight`, which is placed after this extension in
+		// `SignedExtra`, so the block weight limit checked by `CheckWeight` covers it
+		if !currencies_tried.is_zero() {
+			<system::Module<T>>::register_extra_weight_unchecked(
+				<T as Trait>::WeightInfo::withdraw_fee(currencies_tried),
+				info.class,
+			);
+		}
+
+		let call_index = call.using_encoded(Module::<T>::call_index_of).unwrap_or_default();
+		Ok((tip, fee_payer, imbalance, fee, fee_swap, call_index, fee_ratios))
 	}
 
 	fn post_dispatch(
@@ -527,31 +3373,84 @@ where
 		len: usize,
 		_result: &DispatchResult,
 	) -> Result<(), TransactionValidityError> {
-		let (tip, who, imbalance, fee) = pre;
+		let (tip, who, imbalance, fee, fee_swap, call_index, (multiplier, policy_ratio)) = pre;
 		if let Some(payed) = imbalance {
+			// the amount of the currency finally paid for the fee
+			let mut currency_amount: Option<Balance> = fee_swap.map(|(_, supply_amount, _)| supply_amount);
+			// the swap finally kept for the fee, excluding the part swapped back
+			let mut kept_fee_swap = fee_swap;
+			// the actual fee is multiplied and adjusted by the fee policy the same as the
+			// fee withdrawn
+			let actual_base_fee =
+				<pallet_transaction_payment::Module<T>>::compute_actual_fee(len as u32, info, post_info, Zero::zero());
 			let actual_fee =
-				<pallet_transaction_payment::Module<T>>::compute_actual_fee(len as u32, info, post_info, tip);
+				policy_ratio.saturating_mul_int(multiplier.saturating_mul_int(actual_base_fee).saturating_add(tip));
 			let refund = fee.saturating_sub(actual_fee);
 			let actual_payment =
 				match <T as pallet_transaction_payment::Trait>::Currency::deposit_into_existing(&who, refund) {
 					Ok(refund_imbalance) => {
 						// The refund cannot be larger than the up front payed max weight.
 						// `PostDispatchInfo::calc_unspent` guards against such a case.
-						match payed.offset(refund_imbalance) {
+						let actual_payment = match payed.offset(refund_imbalance) {
 							Ok(actual_payment) => actual_payment,
 							Err(_) => return Err(InvalidTransaction::Payment.into()),
+						};
+
+						// refund in the currency which is swapped to pay fee, keep native
+						// refund if the reverse swap fails. Only the swapped part of the fee
+						// is swapped back, the rest of the refund was paid by native currency.
+						if let Some((currency_id, supply_amount, native_amount)) = fee_swap {
+							let refund: Balance = refund.unique_saturated_into();
+							let currency_refund = Module::<T>::swap_refund_back(
+								&who,
+								currency_id,
+								refund.min(native_amount),
+								supply_amount,
+								native_amount,
+							);
+							currency_amount = Some(supply_amount.saturating_sub(currency_refund));
+							if !currency_refund.is_zero() {
+								kept_fee_swap = Some((
+									currency_id,
+									supply_amount.saturating_sub(currency_refund),
+									native_amount.saturating_sub(refund),
+								));
+							}
 						}
+						actual_payment
+					}
+					// We do not recreate the account using the refund. The refund is routed
+					// to `LostRefunds` in that case.
+					Err(_) => {
+						let (lost_refund, actual_payment) = payed.split(refund);
+						if !lost_refund.peek().is_zero() {
+							Module::<T>::deposit_event_at(
+								EventLevel::Errors,
+								RawEvent::RefundLost(who.clone(), lost_refund.peek().unique_saturated_into()),
+							);
+						}
+						T::LostRefunds::on_unbalanced(lost_refund);
+						actual_payment
 					}
-					// We do not recreate the account using the refund. The up front payment
-					// is gone in that case.
-					Err(_) => payed,
 				};
-			let imbalances = actual_payment.split(tip);
-
-			// distribute fee by `pallet_transaction_payment`
-			<T as pallet_transaction_payment::Trait>::OnTransactionPayment::on_unbalanceds(
-				Some(imbalances.0).into_iter().chain(Some(imbalances.1)),
+			let fee_currency_id = fee_swap.map_or_else(T::NativeCurrencyId::get, |(currency_id, _, _)| currency_id);
+			let native_fee: Balance = actual_payment.peek().unique_saturated_into();
+			Module::<T>::record_fee_collected(native_fee.saturating_sub(tip.unique_saturated_into()), kept_fee_swap);
+			Module::<T>::deposit_event_at(
+				EventLevel::Normal,
+				RawEvent::FeeCharged(
+					who.clone(),
+					<system::Module<T>>::block_number(),
+					call_index,
+					native_fee,
+					fee_currency_id,
+					currency_amount.unwrap_or(native_fee),
+				),
 			);
+			let (tip, fee) = actual_payment.split(tip);
+
+			// distribute fee to block author, referrer and `pallet_transaction_payment`
+			Module::<T>::distribute_fee(&who, fee, tip);
 		}
 		Ok(())
 	}
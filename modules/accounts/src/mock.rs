@@ -7,9 +7,14 @@ use frame_support::{
 	impl_outer_dispatch, impl_outer_event, impl_outer_origin, ord_parameter_types, parameter_types,
 	weights::IdentityFee,
 };
+use frame_system::EnsureSignedBy;
 use primitives::{Amount, TokenSymbol, TradingPair};
 use sp_core::H256;
-use sp_runtime::{testing::Header, traits::IdentityLookup, FixedPointNumber, Perbill};
+use sp_runtime::{
+	testing::Header,
+	traits::{IdentityLookup, Lazy},
+	ConsensusEngineId, FixedPointNumber, Perbill,
+};
 use sp_std::cell::RefCell;
 use support::Ratio;
 
@@ -19,13 +24,20 @@ pub type BlockNumber = u64;
 pub const ALICE: AccountId = 1;
 pub const BOB: AccountId = 2;
 pub const CAROL: AccountId = 3;
+pub const DAVE: AccountId = 4;
 pub const ACA: CurrencyId = CurrencyId::Token(TokenSymbol::ACA);
 pub const AUSD: CurrencyId = CurrencyId::Token(TokenSymbol::AUSD);
 pub const BTC: CurrencyId = CurrencyId::Token(TokenSymbol::XBTC);
+pub const DOT: CurrencyId = CurrencyId::Token(TokenSymbol::DOT);
+pub const VESTING_LOCK_ID: LockIdentifier = *b"ormlvest";
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Runtime;
 
+mod accounts {
+	pub use super::super::*;
+}
+
 impl_outer_origin! {
 	pub enum Origin for Runtime {}
 }
@@ -44,6 +56,7 @@ impl_outer_event! {
 		pallet_balances<T>,
 		orml_currencies<T>,
 		dex<T>,
+		accounts<T>,
 	}
 }
 
@@ -73,11 +86,11 @@ impl frame_system::Trait for Runtime {
 	type PalletInfo = ();
 	type AccountData = pallet_balances::AccountData<Balance>;
 	type OnNewAccount = ();
-	type OnKilledAccount = ();
+	type OnKilledAccount = Accounts;
 	type DbWeight = ();
 	type BlockExecutionWeight = ();
 	type ExtrinsicBaseWeight = ();
-	type MaximumExtrinsicWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
 	type BaseCallFilter = ();
 	type SystemWeightInfo = ();
 }
@@ -93,15 +106,11 @@ impl orml_tokens::Trait for Runtime {
 }
 pub type Tokens = orml_tokens::Module<Runtime>;
 
-parameter_types! {
-	pub const ExistentialDeposit: Balance = 0;
-}
-
 impl pallet_balances::Trait for Runtime {
 	type Balance = Balance;
 	type DustRemoval = ();
 	type Event = TestEvent;
-	type ExistentialDeposit = ExistentialDeposit;
+	type ExistentialDeposit = MockExistentialDeposit;
 	type AccountStore = Accounts;
 	type MaxLocks = ();
 	type WeightInfo = ();
@@ -137,6 +146,242 @@ impl pallet_transaction_payment::Trait for Runtime {
 
 thread_local! {
 	static IS_SHUTDOWN: RefCell<bool> = RefCell::new(false);
+	static BLOCK_AUTHOR: RefCell<Option<AccountId>> = RefCell::new(None);
+	static LOST_REFUNDS: RefCell<Balance> = RefCell::new(0);
+	static EVENT_VERBOSITY: RefCell<EventLevel> = RefCell::new(EventLevel::Verbose);
+	static FEE_DECISION: RefCell<Option<FeeDecision<Balance>>> = RefCell::new(None);
+	static DEPOSIT_CURRENCY_ID: RefCell<CurrencyId> = RefCell::new(ACA);
+	static CONSUMERS: RefCell<Vec<AccountId>> = RefCell::new(vec![]);
+	static PROVIDERS: RefCell<Vec<AccountId>> = RefCell::new(vec![]);
+	static OPENED_VIA_SWAP: RefCell<Vec<(AccountId, CurrencyId)>> = RefCell::new(vec![]);
+	static FEE_ORDER: RefCell<MockFeeOrder> = RefCell::new(MockFeeOrder::Fixed);
+	static FREE_QUOTA: RefCell<u32> = RefCell::new(0);
+	static ERA: RefCell<EraIndex> = RefCell::new(0);
+	static MIN_SWAP_AMOUNTS: RefCell<Vec<(CurrencyId, Balance)>> = RefCell::new(vec![]);
+	static KILLED_ACCOUNTS: RefCell<Vec<(AccountId, KillReason)>> = RefCell::new(vec![]);
+	static MULTISIG: RefCell<Option<AccountId>> = RefCell::new(None);
+	static MAX_FEE_SWAPS_PER_BLOCK: RefCell<u32> = RefCell::new(u32::max_value());
+	static MAX_CLOSES_PER_BLOCK: RefCell<u32> = RefCell::new(u32::max_value());
+	static DUST_BURN_RATIO: RefCell<Ratio> = RefCell::new(Ratio::saturating_from_rational(0, 100));
+	static NON_NATIVE_DEPOSIT: RefCell<Option<Balance>> = RefCell::new(None);
+	static EXISTENTIAL_DEPOSIT: RefCell<Balance> = RefCell::new(0);
+}
+
+pub fn set_existential_deposit(deposit: Balance) {
+	EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = deposit);
+}
+
+pub struct MockExistentialDeposit;
+impl Get<Balance> for MockExistentialDeposit {
+	fn get() -> Balance {
+		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_block_author(author: Option<AccountId>) {
+	BLOCK_AUTHOR.with(|v| *v.borrow_mut() = author);
+}
+
+pub fn set_event_verbosity(level: EventLevel) {
+	EVENT_VERBOSITY.with(|v| *v.borrow_mut() = level);
+}
+
+pub fn set_fee_decision(decision: Option<FeeDecision<Balance>>) {
+	FEE_DECISION.with(|v| *v.borrow_mut() = decision);
+}
+
+pub fn set_deposit_currency_id(currency_id: CurrencyId) {
+	DEPOSIT_CURRENCY_ID.with(|v| *v.borrow_mut() = currency_id);
+}
+
+pub struct MockDepositCurrencyId;
+impl Get<CurrencyId> for MockDepositCurrencyId {
+	fn get() -> CurrencyId {
+		DEPOSIT_CURRENCY_ID.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_non_native_deposit(deposit: Option<Balance>) {
+	NON_NATIVE_DEPOSIT.with(|v| *v.borrow_mut() = deposit);
+}
+
+pub struct MockNonNativeDeposit;
+impl Get<Option<Balance>> for MockNonNativeDeposit {
+	fn get() -> Option<Balance> {
+		NON_NATIVE_DEPOSIT.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_account_references(consumers: Vec<AccountId>, providers: Vec<AccountId>) {
+	CONSUMERS.with(|v| *v.borrow_mut() = consumers);
+	PROVIDERS.with(|v| *v.borrow_mut() = providers);
+}
+
+pub struct MockAccountReferences;
+impl AccountReferences<AccountId> for MockAccountReferences {
+	fn has_consumers(who: &AccountId) -> bool {
+		CONSUMERS.with(|v| v.borrow().contains(who))
+	}
+
+	fn has_providers(who: &AccountId) -> bool {
+		PROVIDERS.with(|v| v.borrow().contains(who))
+	}
+}
+
+pub fn opened_via_swap() -> Vec<(AccountId, CurrencyId)> {
+	OPENED_VIA_SWAP.with(|v| v.borrow().clone())
+}
+
+pub struct MockOnAccountOpenedViaSwap;
+impl Happened<(AccountId, CurrencyId)> for MockOnAccountOpenedViaSwap {
+	fn happened(t: &(AccountId, CurrencyId)) {
+		OPENED_VIA_SWAP.with(|v| v.borrow_mut().push(*t));
+	}
+}
+
+pub fn killed_accounts() -> Vec<(AccountId, KillReason)> {
+	KILLED_ACCOUNTS.with(|v| v.borrow().clone())
+}
+
+pub struct MockOnKillAccount;
+impl Happened<(AccountId, KillReason)> for MockOnKillAccount {
+	fn happened(t: &(AccountId, KillReason)) {
+		KILLED_ACCOUNTS.with(|v| v.borrow_mut().push(*t));
+	}
+}
+
+#[derive(Clone, Copy)]
+pub enum MockFeeOrder {
+	Fixed,
+	ByUserBalance,
+	ByDexLiquidity,
+}
+
+pub fn set_fee_order(order: MockFeeOrder) {
+	FEE_ORDER.with(|v| *v.borrow_mut() = order);
+}
+
+pub struct MockFeeCurrencyOrder;
+impl FeeOrderStrategy<Runtime> for MockFeeCurrencyOrder {
+	fn order(who: &AccountId, currency_ids: Vec<CurrencyId>) -> Vec<CurrencyId> {
+		match FEE_ORDER.with(|v| *v.borrow()) {
+			MockFeeOrder::Fixed => <FixedOrder as FeeOrderStrategy<Runtime>>::order(who, currency_ids),
+			MockFeeOrder::ByUserBalance => <ByUserBalance as FeeOrderStrategy<Runtime>>::order(who, currency_ids),
+			MockFeeOrder::ByDexLiquidity => <ByDexLiquidity as FeeOrderStrategy<Runtime>>::order(who, currency_ids),
+		}
+	}
+}
+
+pub fn set_free_quota(quota: u32) {
+	FREE_QUOTA.with(|v| *v.borrow_mut() = quota);
+}
+
+pub struct MockFreeQuotaPerEra;
+impl Get<u32> for MockFreeQuotaPerEra {
+	fn get() -> u32 {
+		FREE_QUOTA.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_era(era: EraIndex) {
+	ERA.with(|v| *v.borrow_mut() = era);
+}
+
+pub struct MockEraProvider;
+impl Get<EraIndex> for MockEraProvider {
+	fn get() -> EraIndex {
+		ERA.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_min_swap_amounts(amounts: Vec<(CurrencyId, Balance)>) {
+	MIN_SWAP_AMOUNTS.with(|v| *v.borrow_mut() = amounts);
+}
+
+pub struct MockMinSwapAmounts;
+impl Get<Vec<(CurrencyId, Balance)>> for MockMinSwapAmounts {
+	fn get() -> Vec<(CurrencyId, Balance)> {
+		MIN_SWAP_AMOUNTS.with(|v| v.borrow().clone())
+	}
+}
+
+pub fn set_max_fee_swaps_per_block(max: u32) {
+	MAX_FEE_SWAPS_PER_BLOCK.with(|v| *v.borrow_mut() = max);
+}
+
+pub struct MockMaxFeeSwapsPerBlock;
+impl Get<u32> for MockMaxFeeSwapsPerBlock {
+	fn get() -> u32 {
+		MAX_FEE_SWAPS_PER_BLOCK.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_max_closes_per_block(max: u32) {
+	MAX_CLOSES_PER_BLOCK.with(|v| *v.borrow_mut() = max);
+}
+
+pub struct MockMaxClosesPerBlock;
+impl Get<u32> for MockMaxClosesPerBlock {
+	fn get() -> u32 {
+		MAX_CLOSES_PER_BLOCK.with(|v| *v.borrow())
+	}
+}
+
+pub fn set_dust_burn_ratio(ratio: Ratio) {
+	DUST_BURN_RATIO.with(|v| *v.borrow_mut() = ratio);
+}
+
+pub struct MockDustBurnRatio;
+impl Get<Ratio> for MockDustBurnRatio {
+	fn get() -> Ratio {
+		DUST_BURN_RATIO.with(|v| *v.borrow())
+	}
+}
+
+pub struct MockCallFeePolicy;
+impl CallFeePolicy<Call, Balance> for MockCallFeePolicy {
+	fn decide(_call: &Call, fee: Balance) -> FeeDecision<Balance> {
+		FEE_DECISION.with(|v| v.borrow().unwrap_or(FeeDecision::Charge(fee)))
+	}
+}
+
+pub fn set_multisig(multisig: Option<AccountId>) {
+	MULTISIG.with(|v| *v.borrow_mut() = multisig);
+}
+
+pub struct MockMultisigApproval;
+impl MultisigApproval<AccountId, Call> for MockMultisigApproval {
+	fn multisig_of(_who: &AccountId, _call: &Call) -> Option<AccountId> {
+		MULTISIG.with(|v| *v.borrow())
+	}
+}
+
+pub struct MockEventVerbosity;
+impl Get<EventLevel> for MockEventVerbosity {
+	fn get() -> EventLevel {
+		EVENT_VERBOSITY.with(|v| *v.borrow())
+	}
+}
+
+pub fn lost_refunds() -> Balance {
+	LOST_REFUNDS.with(|v| *v.borrow())
+}
+
+pub struct MockLostRefunds;
+impl OnUnbalanced<NegativeImbalanceOf<Runtime>> for MockLostRefunds {
+	fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<Runtime>) {
+		LOST_REFUNDS.with(|v| *v.borrow_mut() += amount.peek());
+	}
+}
+
+pub struct MockFindAuthor;
+impl FindAuthor<AccountId> for MockFindAuthor {
+	fn find_author<'a, I>(_digests: I) -> Option<AccountId>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		BLOCK_AUTHOR.with(|v| *v.borrow())
+	}
 }
 
 ord_parameter_types! {
@@ -147,7 +392,13 @@ parameter_types! {
 	pub const DEXModuleId: ModuleId = ModuleId(*b"aca/dexm");
 	pub const GetExchangeFee: (u32, u32) = (0, 100);
 	pub const TradingPathLimit: usize = 3;
-	pub EnabledTradingPairs : Vec<TradingPair> = vec![TradingPair::new(AUSD, ACA), TradingPair::new(AUSD, BTC)];
+	pub EnabledTradingPairs : Vec<TradingPair> = vec![
+		TradingPair::new(AUSD, ACA),
+		TradingPair::new(AUSD, BTC),
+		TradingPair::new(ACA, BTC),
+		TradingPair::new(DOT, ACA),
+		TradingPair::new(DOT, BTC),
+	];
 }
 
 impl dex::Trait for Runtime {
@@ -161,25 +412,140 @@ impl dex::Trait for Runtime {
 }
 pub type DEXModule = dex::Module<Runtime>;
 
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct MockSignature(pub AccountId, pub Vec<u8>);
+
+impl Verify for MockSignature {
+	type Signer = MockSigner;
+
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &AccountId) -> bool {
+		self.0 == *signer && self.1[..] == *msg.get()
+	}
+}
+
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct MockSigner(pub AccountId);
+
+impl IdentifyAccount for MockSigner {
+	type AccountId = AccountId;
+
+	fn into_account(self) -> AccountId {
+		self.0
+	}
+}
+
+pub struct MockOpenDepositDiscount;
+impl OpenDepositDiscount<AccountId> for MockOpenDepositDiscount {
+	fn discount(who: &AccountId) -> Perbill {
+		// DAVE is regarded as a staked account
+		if *who == DAVE {
+			Perbill::from_percent(50)
+		} else {
+			Perbill::zero()
+		}
+	}
+}
+
+pub struct MockTransferGuard;
+impl TransferGuard<AccountId> for MockTransferGuard {
+	fn allow(currency_id: CurrencyId, _from: &AccountId, to: &AccountId) -> bool {
+		// DAVE is not allowed to receive BTC
+		!(currency_id == BTC && *to == DAVE)
+	}
+}
+
 parameter_types! {
 	pub AllNonNativeCurrencyIds: Vec<CurrencyId> = vec![AUSD, BTC];
 	pub const NewAccountDeposit: Balance = 100;
 	pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
 	pub MaxSlippageSwapWithDEX: Ratio = Ratio::one();
 	pub const StableCurrencyId: CurrencyId = AUSD;
+	pub const BlockAuthorShare: Perbill = Perbill::from_percent(20);
+	pub const TipToBlockAuthor: bool = true;
+	pub const MaxConsolidateSources: u32 = 3;
+	pub const PendingCloseWindow: BlockNumber = 10;
+	pub const MaxPendingCloses: u32 = 2;
+	pub const CloseRequestDeposit: Balance = 50;
+	pub FeeReserveFloors: Vec<(CurrencyId, Balance)> = vec![(AUSD, 500)];
+	pub const FeePreferenceCooldown: BlockNumber = 10;
+	pub const ZeroFeePriority: TransactionPriority = 7;
+	pub const FeePathCacheLifetime: BlockNumber = 10;
+	pub const ReferralShare: Perbill = Perbill::from_percent(10);
+	pub const MaxReferralPayoutPerEra: Balance = 150;
+	pub const MaxRecentlyKilled: u32 = 2;
+	pub const MaxFeeSwapAmount: Balance = 500;
+	pub const MaxCloseBatch: u32 = 3;
+	pub const DustThreshold: Balance = 80;
+	pub const OpenAttemptCooldown: BlockNumber = 5;
+	pub const FeeReliefFundModuleId: ModuleId = ModuleId(*b"aca/frlf");
+	pub const FeeReliefEnabled: bool = true;
+	pub const MaxFeeReliefDebt: Balance = 500;
+	pub ExcludedFromAutoOpen: Vec<AccountId> = vec![FeeReliefFundModuleId::get().into_account()];
+	pub const MaxFeeSwapHops: u32 = 2;
+	pub StableCurrencyIds: Vec<CurrencyId> = vec![AUSD, DOT];
+	pub FeeLockLimits: Vec<(LockIdentifier, Balance)> = vec![(VESTING_LOCK_ID, 500)];
 }
 
 impl Trait for Runtime {
+	type Event = TestEvent;
 	type AllNonNativeCurrencyIds = AllNonNativeCurrencyIds;
 	type NativeCurrencyId = GetNativeCurrencyId;
 	type StableCurrencyId = StableCurrencyId;
+	type StableCurrencyIds = StableCurrencyIds;
 	type Currency = Currencies;
 	type DEX = DEXModule;
 	type OnCreatedAccount = frame_system::CallOnCreatedAccount<Runtime>;
+	type OnAccountOpenedViaSwap = MockOnAccountOpenedViaSwap;
 	type KillAccount = frame_system::CallKillAccount<Runtime>;
+	type OnKillAccount = MockOnKillAccount;
 	type NewAccountDeposit = NewAccountDeposit;
+	type OpenDepositDiscount = MockOpenDepositDiscount;
 	type TreasuryModuleId = TreasuryModuleId;
 	type MaxSlippageSwapWithDEX = MaxSlippageSwapWithDEX;
+	type FindAuthor = MockFindAuthor;
+	type BlockAuthorShare = BlockAuthorShare;
+	type TipToBlockAuthor = TipToBlockAuthor;
+	type MaxConsolidateSources = MaxConsolidateSources;
+	type LostRefunds = MockLostRefunds;
+	type PendingCloseWindow = PendingCloseWindow;
+	type MaxPendingCloses = MaxPendingCloses;
+	type CloseRequestDeposit = CloseRequestDeposit;
+	type MaxClosesPerBlock = MockMaxClosesPerBlock;
+	type FeeReserveFloors = FeeReserveFloors;
+	type MinSwapAmounts = MockMinSwapAmounts;
+	type EventVerbosity = MockEventVerbosity;
+	type TransferGuard = MockTransferGuard;
+	type CallFeePolicy = MockCallFeePolicy;
+	type MultisigApproval = MockMultisigApproval;
+	type FeeLockAllowance = BalancesFeeLockAllowance<Runtime, FeeLockLimits>;
+	type FeeCurrencyOrder = MockFeeCurrencyOrder;
+	type ExcludedFromAutoOpen = ExcludedFromAutoOpen;
+	type FreeQuotaPerEra = MockFreeQuotaPerEra;
+	type EraProvider = MockEraProvider;
+	type Signature = MockSignature;
+	type Signer = MockSigner;
+	type FeePreferenceCooldown = FeePreferenceCooldown;
+	type ZeroFeePriority = ZeroFeePriority;
+	type FeePathCacheLifetime = FeePathCacheLifetime;
+	type ReferralShare = ReferralShare;
+	type MaxReferralPayoutPerEra = MaxReferralPayoutPerEra;
+	type MaxRecentlyKilled = MaxRecentlyKilled;
+	type UpdateOrigin = EnsureSignedBy<Zero, AccountId>;
+	type CloseOrigin = EnsureSignedBy<Zero, AccountId>;
+	type ForceCloseOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxCloseBatch = MaxCloseBatch;
+	type MaxFeeSwapAmount = MaxFeeSwapAmount;
+	type MaxFeeSwapHops = MaxFeeSwapHops;
+	type MaxFeeSwapsPerBlock = MockMaxFeeSwapsPerBlock;
+	type DustThreshold = DustThreshold;
+	type DustBurnRatio = MockDustBurnRatio;
+	type DepositCurrencyId = MockDepositCurrencyId;
+	type NonNativeDeposit = MockNonNativeDeposit;
+	type AccountReferences = MockAccountReferences;
+	type OpenAttemptCooldown = OpenAttemptCooldown;
+	type FeeReliefFund = FeeReliefFundModuleId;
+	type FeeReliefEnabled = FeeReliefEnabled;
+	type MaxFeeReliefDebt = MaxFeeReliefDebt;
 	type WeightInfo = ();
 }
 pub type Accounts = Module<Runtime>;
@@ -191,7 +557,7 @@ pub struct ExtBuilder {
 impl Default for ExtBuilder {
 	fn default() -> Self {
 		Self {
-			endowed_accounts: vec![(ALICE, AUSD, 10000), (ALICE, BTC, 1000)],
+			endowed_accounts: vec![(ALICE, AUSD, 10000), (ALICE, BTC, 1000), (ALICE, DOT, 10000)],
 		}
 	}
 }
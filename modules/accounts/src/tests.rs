@@ -5,13 +5,21 @@
 use super::*;
 use frame_support::{
 	assert_noop, assert_ok,
+	traits::{LockableCurrency, OnFinalize, OnInitialize, OnRuntimeUpgrade},
 	weights::{DispatchClass, DispatchInfo, Pays},
 };
 use mock::{
-	Accounts, Call, Currencies, DEXModule, ExtBuilder, NewAccountDeposit, Origin, Runtime, System, ACA, ALICE, AUSD,
-	BOB, BTC, CAROL,
+	killed_accounts, lost_refunds, opened_via_swap, set_account_references, set_block_author, set_deposit_currency_id,
+	set_dust_burn_ratio, set_era, set_event_verbosity, set_existential_deposit, set_fee_decision, set_fee_order,
+	set_free_quota, set_max_closes_per_block, set_max_fee_swaps_per_block, set_min_swap_amounts, set_multisig,
+	set_non_native_deposit, AccountId, Accounts, Call, Currencies, DEXModule, DustThreshold, ExtBuilder,
+	MaxFeeSwapHops, MaxSlippageSwapWithDEX, MockFeeOrder, MockSignature, NewAccountDeposit, Origin, PalletBalances,
+	Runtime, System, TestEvent, ZeroFeePriority, ACA, ALICE, AUSD, BOB, BTC, CAROL, DAVE, DOT, VESTING_LOCK_ID,
 };
 use orml_traits::MultiCurrency;
+use primitives::TradingPair;
+use sp_core::H256;
+use sp_runtime::{traits::BadOrigin, FixedPointNumber};
 
 const CALL: &<Runtime as system::Trait>::Call = &Call::Currencies(orml_currencies::Call::transfer(BOB, AUSD, 12));
 
@@ -38,7 +46,7 @@ fn charges_fee() {
 				.validate(&ALICE, CALL, &INFO, 23)
 				.unwrap()
 				.priority,
-			fee
+			fee * 1024 / 1000 // fee * MaximumBlockWeight / weight
 		);
 		assert_eq!(Currencies::free_balance(ACA, &ALICE), (100000 - fee).into());
 
@@ -48,7 +56,7 @@ fn charges_fee() {
 				.validate(&ALICE, CALL2, &INFO, 18)
 				.unwrap()
 				.priority,
-			fee2
+			fee2 * 1024 / 1000
 		);
 		assert_eq!(
 			Currencies::free_balance(ACA, &ALICE),
@@ -57,6 +65,79 @@ fn charges_fee() {
 	});
 }
 
+#[test]
+fn usable_native_balance_excludes_locks() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		assert_eq!(Accounts::usable_native_balance(&BOB), 900);
+
+		// the lock does not block transaction payment
+		PalletBalances::set_lock(*b"1       ", &BOB, 300, WithdrawReason::Transfer.into());
+		assert_eq!(Accounts::usable_native_balance(&BOB), 900);
+
+		assert_eq!(System::block_weight().total(), 0);
+
+		// the binary search is registered as extra weight
+		PalletBalances::set_lock(*b"2       ", &BOB, 300, WithdrawReasons::all());
+		assert_eq!(Accounts::usable_native_balance(&BOB), 600);
+		assert_eq!(
+			System::block_weight().total(),
+			<() as WeightInfo>::usable_native_balance()
+		);
+
+		PalletBalances::set_lock(*b"2       ", &BOB, 1000, WithdrawReasons::all());
+		assert_eq!(Accounts::usable_native_balance(&BOB), 0);
+		assert_eq!(
+			Accounts::usable_native_balance_for(&BOB, WithdrawReason::Transfer.into()),
+			0
+		);
+	});
+}
+
+#[test]
+fn charges_fee_from_allowed_lock() {
+	ExtBuilder::default().build().execute_with(|| {
+		let fee = 23 * 2 + 1000; // len * byte + weight
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 2000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 2000));
+
+		// the gap of 546 exceeds the limit of vesting lock
+		PalletBalances::set_lock(VESTING_LOCK_ID, &BOB, 1400, WithdrawReasons::all());
+		assert_eq!(Accounts::usable_native_balance(&BOB), 500);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.is_err());
+		assert_eq!(PalletBalances::locks(&BOB)[0].amount, 1400);
+
+		// the gap of 446 is released from vesting lock
+		PalletBalances::set_lock(VESTING_LOCK_ID, &BOB, 1300, WithdrawReasons::all());
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 1900 - fee);
+		assert_eq!(PalletBalances::locks(&BOB)[0].amount, 1300 - 446);
+		assert_eq!(Accounts::usable_native_balance(&BOB), 0);
+
+		// the reasons of the released lock are kept
+		PalletBalances::set_lock(VESTING_LOCK_ID, &BOB, 954, WithdrawReason::TransactionPayment.into());
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1100));
+		assert_eq!(Accounts::usable_native_balance(&BOB), 1000);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(PalletBalances::locks(&BOB)[0].amount, 954 - 46);
+		assert_eq!(PalletBalances::locks(&BOB)[0].reasons, pallet_balances::Reasons::Fee);
+
+		// the lock not allowed still blocks the fee
+		PalletBalances::set_lock(VESTING_LOCK_ID, &CAROL, 1300, WithdrawReasons::all());
+		PalletBalances::set_lock(*b"1       ", &CAROL, 1300, WithdrawReasons::all());
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&CAROL, CALL, &INFO, 23)
+			.is_err());
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 1900);
+	});
+}
+
 #[test]
 fn charges_fee_when_pre_dispatch_and_native_currency_is_enough() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -83,6 +164,118 @@ fn refund_fee_according_to_actual_when_post_dispatch_and_native_currency_is_enou
 	});
 }
 
+#[test]
+fn distribute_fee_to_block_author_when_post_dispatch() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 99000);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 900);
+
+		// no block author, all fee goes to `OnTransactionPayment`
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 99000 - 850);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 900);
+
+		// block author receives 20% of fee and the whole tip
+		set_block_author(Some(BOB));
+		let pre = ChargeTransactionPayment::<Runtime>::from(100)
+			.pre_dispatch(&ALICE, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 99000 - 850 - 950);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 900 + 170 + 100);
+	});
+}
+
+#[test]
+fn distribute_fee_to_referrer_when_post_dispatch() {
+	ExtBuilder::default().build().execute_with(|| {
+		// the referrer is recorded when the account is opened
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_noop!(
+			Accounts::try_open_account(Origin::signed(BOB), Some(BOB)),
+			Error::<Runtime>::InvalidReferrer,
+		);
+		assert_ok!(Accounts::try_open_account(Origin::signed(BOB), Some(CAROL)));
+		assert_eq!(Accounts::referrer(&BOB), Some(CAROL));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 2900));
+		let bob_balance = Currencies::free_balance(ACA, &BOB);
+
+		// unreferred account's fee does not credit referrer
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 0);
+
+		// the payout below the existential deposit is not paid to non-existent referrer
+		set_existential_deposit(86);
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), bob_balance - 850);
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 0);
+		assert_eq!(Accounts::referral_payouts(), (0, 0));
+		set_existential_deposit(0);
+
+		// referrer receives 10% of fee
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), bob_balance - 850 - 850);
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 85);
+		assert_eq!(Accounts::referral_payouts(), (0, 85));
+
+		// referral payouts are capped in an era
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 150);
+		assert_eq!(Accounts::referral_payouts(), (0, 150));
+
+		// the cap is reset in next era
+		set_era(1);
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 25)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 25, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 235);
+		assert_eq!(Accounts::referral_payouts(), (1, 85));
+	});
+}
+
+#[test]
+fn refund_to_reaped_account_is_routed_to_lost_refunds() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 2000));
+		let fee = 23 * 2 + 1000; // len * byte + weight
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.unwrap();
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 1900 - fee);
+
+		// BOB is reaped during dispatch
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+
+		assert_eq!(lost_refunds(), 0);
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 23, &Ok(())).is_ok());
+		assert_eq!(lost_refunds(), 200);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+
+		let refund_lost_event = TestEvent::accounts(RawEvent::RefundLost(BOB, 200));
+		assert!(System::events().iter().any(|record| record.event == refund_lost_event));
+	});
+}
+
 #[test]
 fn open_account_successfully_when_transfer_native() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -112,6 +305,160 @@ fn open_account_failed_when_transfer_native() {
 	});
 }
 
+#[test]
+fn burn_portion_of_swept_dust() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let treasury = Accounts::treasury_account_id();
+
+		// nothing is burned by default
+		let issuance = PalletBalances::total_issuance();
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 50));
+		assert_eq!(Currencies::free_balance(ACA, &treasury), 50);
+		assert_eq!(PalletBalances::total_issuance(), issuance);
+
+		// half is burned
+		set_dust_burn_ratio(Ratio::saturating_from_rational(50, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 50));
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+		assert_eq!(Currencies::free_balance(ACA, &treasury), 75);
+		assert_eq!(PalletBalances::total_issuance(), issuance - 25);
+		let dust_swept_event = TestEvent::accounts(RawEvent::DustSwept(CAROL, 25));
+		assert!(System::events().iter().any(|record| record.event == dust_swept_event));
+		let dust_burned_event = TestEvent::accounts(RawEvent::DustBurned(CAROL, 25));
+		assert!(System::events().iter().any(|record| record.event == dust_burned_event));
+
+		// all is burned
+		set_dust_burn_ratio(Ratio::one());
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 50));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Currencies::free_balance(ACA, &treasury), 75);
+		assert_eq!(PalletBalances::total_issuance(), issuance - 75);
+		assert_eq!(Accounts::total_swept_dust(), 75);
+		let dust_burned_event = TestEvent::accounts(RawEvent::DustBurned(BOB, 50));
+		assert!(System::events().iter().any(|record| record.event == dust_burned_event));
+	});
+}
+
+#[test]
+fn swept_dust_summary_on_finalize() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 50));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 20));
+		assert_eq!(Accounts::swept_dust(), 70);
+		assert_eq!(Accounts::total_swept_dust(), 70);
+		let dust_swept_event = TestEvent::accounts(RawEvent::DustSwept(BOB, 50));
+		assert!(System::events().iter().any(|record| record.event == dust_swept_event));
+
+		Accounts::on_finalize(1);
+		assert_eq!(Accounts::swept_dust(), 0);
+		let dust_summary_event = TestEvent::accounts(RawEvent::DustSummary(70));
+		assert!(System::events().iter().any(|record| record.event == dust_summary_event));
+
+		// the total is accumulated across blocks
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &DAVE, 10));
+		assert_eq!(Accounts::swept_dust(), 10);
+		assert_eq!(Accounts::total_swept_dust(), 80);
+	});
+}
+
+#[test]
+fn open_account_without_deposit_above_dust_threshold() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(DustThreshold::get(), 80);
+
+		// below threshold, reaped as dust
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 79));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(
+			<Currencies as MultiCurrency<_>>::free_balance(ACA, &Accounts::treasury_account_id()),
+			79
+		);
+
+		// at threshold, kept alive without deposit
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 80));
+		assert_eq!(Accounts::is_explicit(&CAROL), true);
+		assert_eq!(Accounts::open_deposits(&CAROL), Some(0));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &CAROL), 80);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &CAROL),
+			0
+		);
+
+		// can be closed normally
+		assert_ok!(Accounts::close_account(Origin::signed(CAROL), Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+	});
+}
+
+#[test]
+fn set_max_slippage_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_eq!(MaxSlippage::exists(), false);
+		assert_eq!(Accounts::max_slippage(), MaxSlippageSwapWithDEX::get());
+		Accounts::on_runtime_upgrade();
+		assert_eq!(MaxSlippage::exists(), true);
+		assert_eq!(Accounts::max_slippage(), Ratio::one());
+
+		let slippage = Ratio::saturating_from_rational(5, 100);
+		assert_noop!(Accounts::set_max_slippage(Origin::signed(ALICE), slippage), BadOrigin);
+		assert_ok!(Accounts::set_max_slippage(Origin::signed(0), slippage));
+		assert_eq!(Accounts::max_slippage(), slippage);
+		let max_slippage_updated_event = TestEvent::accounts(RawEvent::MaxSlippageUpdated(slippage));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == max_slippage_updated_event));
+
+		// the per-currency limit still overrides it
+		assert_eq!(Accounts::slippage_limit_of(AUSD), slippage);
+		assert_ok!(Accounts::set_currency_slippage(
+			Origin::signed(0),
+			AUSD,
+			Some(Ratio::one())
+		));
+		assert_eq!(Accounts::slippage_limit_of(AUSD), Ratio::one());
+		assert_eq!(Accounts::slippage_limit_of(BTC), slippage);
+	});
+}
+
+#[test]
+fn set_new_account_deposit_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_eq!(NewAccountDepositAmount::exists(), false);
+		assert_eq!(Accounts::new_account_deposit(), NewAccountDeposit::get());
+		Accounts::on_runtime_upgrade();
+		assert_eq!(NewAccountDepositAmount::exists(), true);
+		assert_eq!(Accounts::new_account_deposit(), 100);
+
+		assert_noop!(Accounts::set_new_account_deposit(Origin::signed(ALICE), 200), BadOrigin);
+		assert_ok!(Accounts::set_new_account_deposit(Origin::signed(0), 200));
+		assert_eq!(Accounts::new_account_deposit(), 200);
+		let new_account_deposit_updated_event = TestEvent::accounts(RawEvent::NewAccountDepositUpdated(200));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == new_account_deposit_updated_event));
+
+		// not enough to open with the new deposit
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 150));
+		assert_eq!(Accounts::open_deposits(&BOB), Some(0));
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 300));
+		assert_eq!(Accounts::open_deposits(&CAROL), Some(200));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &CAROL),
+			200
+		);
+		assert_ok!(Accounts::close_account(Origin::signed(CAROL), Some(ALICE)));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &CAROL),
+			0
+		);
+	});
+}
+
 #[test]
 fn open_account_successfully_when_transfer_non_native() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -143,6 +490,104 @@ fn open_account_successfully_when_transfer_non_native() {
 	});
 }
 
+#[test]
+fn notify_account_opened_via_swap() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// opened by native deposit
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 100));
+		assert_eq!(Accounts::is_explicit(&CAROL), true);
+		assert_eq!(opened_via_swap(), vec![]);
+
+		// opened by swapping the received non-native currency
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(opened_via_swap(), vec![(BOB, AUSD)]);
+	});
+}
+
+#[test]
+fn deposit_event_when_deposit_funded_via_swap() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// 11 AUSD is swapped to 100 ACA for the open account deposit
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 89);
+		let deposit_funded_event = TestEvent::accounts(RawEvent::DepositFundedViaSwap(BOB, AUSD, 11, 100));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == deposit_funded_event));
+	});
+}
+
+#[test]
+fn nested_on_received_during_swap_is_short_circuited() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// simulate the DEX calling back into `on_received` during the swap to open BOB
+		SwappingToOpen::<Runtime>::insert(BOB, true);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::last_open_attempt(&BOB), None);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 100);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		// the flag is cleared once the outer swap finished
+		SwappingToOpen::<Runtime>::remove(BOB);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(Accounts::swapping_to_open(&BOB), false);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 189);
+	});
+}
+
+#[test]
+fn open_account_by_custom_fee_swap_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), BTC, AUSD, 10, 200));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, BTC, 10000, 10));
+
+		assert_noop!(
+			Accounts::set_fee_swap_path(Origin::signed(ALICE), BTC, Some(vec![BTC, ACA])),
+			BadOrigin,
+		);
+		assert_noop!(
+			Accounts::set_fee_swap_path(Origin::signed(0), BTC, Some(vec![AUSD, ACA])),
+			Error::<Runtime>::InvalidFeeSwapPath,
+		);
+		assert_noop!(
+			Accounts::set_fee_swap_path(Origin::signed(0), BTC, Some(vec![BTC, AUSD])),
+			Error::<Runtime>::InvalidFeeSwapPath,
+		);
+		assert_ok!(Accounts::set_fee_swap_path(
+			Origin::signed(0),
+			BTC,
+			Some(vec![BTC, ACA])
+		));
+		assert_eq!(Accounts::fee_swap_path(BTC), vec![BTC, ACA]);
+		let fee_swap_path_updated_event = TestEvent::accounts(RawEvent::FeeSwapPathUpdated(BTC, Some(vec![BTC, ACA])));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == fee_swap_path_updated_event));
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, BTC), (9900, 11));
+		assert_eq!(DEXModule::get_liquidity_pool(BTC, AUSD), (10, 200));
+
+		// fallback to the default path
+		assert_ok!(Accounts::set_fee_swap_path(Origin::signed(0), BTC, None));
+		assert_eq!(Accounts::fee_swap_path(BTC), vec![BTC, AUSD, ACA]);
+	});
+}
+
 #[test]
 fn open_account_failed_when_transfer_non_native() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -170,69 +615,290 @@ fn open_account_failed_when_transfer_non_native() {
 }
 
 #[test]
-fn close_account_failed_when_not_allowed_death() {
+fn open_account_by_swapping_other_held_currency() {
 	ExtBuilder::default().build().execute_with(|| {
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
-		System::inc_ref(&BOB);
-		assert_noop!(
-			Accounts::close_account(Origin::signed(BOB), None),
-			Error::<Runtime>::NonZeroRefCount,
+		// BOB holds AUSD but can not be opened without liquidity
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+
+		// only ACA-AUSD has liquidity
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// received BTC after the cooldown of the failed attempt can not be swapped,
+		// fallback to swap the held AUSD
+		System::set_block_number(5);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB), 10);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			100
 		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 89);
 	});
 }
 
 #[test]
-fn close_account_failed_when_still_has_active_reserved() {
+fn skip_auto_open_within_cooldown_after_failed_attempt() {
 	ExtBuilder::default().build().execute_with(|| {
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
-		assert_eq!(System::allow_death(&BOB), true);
-		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(ACA, &BOB, 10));
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 10));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::last_open_attempt(&BOB), Some(1));
+
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// the repeated receipts within cooldown don't try to swap
+		System::set_block_number(5);
+		for _ in 0..3 {
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 10));
+		}
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::last_open_attempt(&BOB), Some(1));
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		System::set_block_number(6);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 10));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(Accounts::last_open_attempt(&BOB), None);
+		Accounts::on_initialize(6);
+		assert_eq!(Accounts::open_attempt_expiries(6), vec![]);
 		assert_eq!(
 			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
-			10 + NewAccountDeposit::get(),
-		);
-		assert_noop!(
-			Accounts::close_account(Origin::signed(BOB), None),
-			Error::<Runtime>::StillHasActiveReserved,
+			100
 		);
+	});
+}
 
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 200));
-		assert_ok!(<Currencies as MultiCurrency<_>>::deposit(BTC, &CAROL, 10));
-		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(BTC, &CAROL, 1));
-		assert_eq!(System::allow_death(&CAROL), true);
-		assert_noop!(
-			Accounts::close_account(Origin::signed(CAROL), None),
-			Error::<Runtime>::StillHasActiveReserved,
-		);
+#[test]
+fn failed_open_attempt_expires_after_cooldown() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 10));
+		assert_eq!(Accounts::last_open_attempt(&BOB), Some(1));
+		assert_eq!(Accounts::open_attempt_expiries(6), vec![BOB]);
+
+		System::set_block_number(3);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &CAROL, 10));
+		assert_eq!(Accounts::open_attempt_expiries(8), vec![CAROL]);
+
+		// the account never opened is pruned once the cooldown expires
+		Accounts::on_initialize(6);
+		assert_eq!(Accounts::last_open_attempt(&BOB), None);
+		assert_eq!(Accounts::last_open_attempt(&CAROL), Some(3));
+		assert_eq!(Accounts::open_attempt_expiries(6), vec![]);
+
+		Accounts::on_initialize(8);
+		assert_eq!(Accounts::last_open_attempt(&CAROL), None);
+		assert_eq!(Accounts::open_attempt_expiries(8), vec![]);
 	});
 }
 
 #[test]
-fn close_account_and_does_not_specific_receiver() {
+fn try_open_account_work() {
 	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
 		assert_eq!(Accounts::is_explicit(&BOB), false);
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_noop!(
+			Accounts::try_open_account(Origin::signed(BOB), None),
+			Error::<Runtime>::CannotOpenAccount,
+		);
+
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(Accounts::try_open_account(Origin::signed(BOB), None));
 		assert_eq!(Accounts::is_explicit(&BOB), true);
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 300));
-		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 400);
 		assert_eq!(
 			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
 			100
 		);
-		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 1000);
-		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB), 300);
-		assert_eq!(
-			<Currencies as MultiCurrency<_>>::free_balance(ACA, &Accounts::treasury_account_id()),
-			0
+		assert_noop!(
+			Accounts::try_open_account(Origin::signed(BOB), None),
+			Error::<Runtime>::AccountAlreadyOpened,
 		);
-		assert_eq!(
-			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &Accounts::treasury_account_id()),
-			0
+
+		// CAROL is kept alive without deposit
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 80));
+		assert_eq!(Accounts::open_deposits(&CAROL), Some(0));
+		assert_noop!(
+			Accounts::try_open_account(Origin::signed(CAROL), None),
+			Error::<Runtime>::CannotOpenAccount,
 		);
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 20));
+		assert_ok!(Accounts::try_open_account(Origin::signed(CAROL), None));
+		assert_eq!(Accounts::open_deposits(&CAROL), Some(100));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &CAROL), 0);
 		assert_eq!(
-			<Currencies as MultiCurrency<_>>::free_balance(AUSD, &Accounts::treasury_account_id()),
-			0
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &CAROL),
+			100
+		);
+	});
+}
+
+#[test]
+fn open_and_close_account_with_non_native_deposit() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_deposit_currency_id(AUSD);
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// the deposit is reserved in AUSD without swap
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(Accounts::deposit_currencies(&BOB), Some(AUSD));
+		assert_eq!(Accounts::open_deposits(&BOB), Some(11));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(AUSD, &BOB),
+			11
+		);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		// the deposit is returned in AUSD
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::deposit_currencies(&BOB), None);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &ALICE), 9000);
+	});
+}
+
+#[test]
+fn open_account_with_non_native_deposit_without_dex() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_deposit_currency_id(DOT);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, DOT), (0, 0));
+
+		// can not open account without native liquidity in DEX
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(DOT, &ALICE, &BOB, 200));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+
+		// the deposit is reserved in the DOT amount configured, not the native amount,
+		// as DOT has different decimals from native currency
+		set_non_native_deposit(Some(5));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(DOT, &ALICE, &CAROL, 200));
+		assert_eq!(Accounts::is_explicit(&CAROL), true);
+		assert_eq!(Accounts::deposit_currencies(&CAROL), Some(DOT));
+		assert_eq!(Accounts::open_deposits(&CAROL), Some(5));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(DOT, &CAROL),
+			5
+		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(DOT, &CAROL), 195);
+
+		// the discount is applied to the non-native deposit
+		set_non_native_deposit(Some(6));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(DOT, &ALICE, &DAVE, 200));
+		assert_eq!(Accounts::open_deposits(&DAVE), Some(3));
+	});
+}
+
+#[test]
+fn close_account_twice_fails_cleanly() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), Some(ALICE)),
+			Error::<Runtime>::AccountAlreadyClosed,
+		);
+	});
+}
+
+#[test]
+fn close_account_failed_when_not_allowed_death() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
+		System::inc_ref(&BOB);
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), None),
+			Error::<Runtime>::HasConsumers,
+		);
+	});
+}
+
+#[test]
+fn can_close_account_with_registered_references() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
+		assert!(Accounts::can_close_account(&BOB).is_ok());
+
+		set_account_references(vec![BOB], vec![]);
+		assert!(matches!(
+			Accounts::can_close_account(&BOB),
+			Err(Error::<Runtime>::HasConsumers)
+		));
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), None),
+			Error::<Runtime>::HasConsumers,
+		);
+
+		set_account_references(vec![], vec![BOB]);
+		assert!(matches!(
+			Accounts::can_close_account(&BOB),
+			Err(Error::<Runtime>::HasProviders)
+		));
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), None),
+			Error::<Runtime>::HasProviders,
+		);
+
+		set_account_references(vec![], vec![]);
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), None));
+	});
+}
+
+#[test]
+fn close_account_failed_when_still_has_active_reserved() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
+		assert_eq!(System::allow_death(&BOB), true);
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(ACA, &BOB, 10));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			10 + NewAccountDeposit::get(),
+		);
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), None),
+			Error::<Runtime>::StillHasActiveReserved,
+		);
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 200));
+		assert_ok!(<Currencies as MultiCurrency<_>>::deposit(BTC, &CAROL, 10));
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(BTC, &CAROL, 1));
+		assert_eq!(System::allow_death(&CAROL), true);
+		assert_noop!(
+			Accounts::close_account(Origin::signed(CAROL), None),
+			Error::<Runtime>::StillHasActiveReserved,
+		);
+	});
+}
+
+#[test]
+fn close_account_and_does_not_specific_receiver() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 300));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 400);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			100
+		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 1000);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB), 300);
+		assert_eq!(
+			<Currencies as MultiCurrency<_>>::free_balance(ACA, &Accounts::treasury_account_id()),
+			0
+		);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &Accounts::treasury_account_id()),
+			0
+		);
+		assert_eq!(
+			<Currencies as MultiCurrency<_>>::free_balance(AUSD, &Accounts::treasury_account_id()),
+			0
 		);
 		assert_eq!(
 			<Currencies as MultiCurrency<_>>::free_balance(BTC, &Accounts::treasury_account_id()),
@@ -331,34 +997,2213 @@ fn close_account_and_specific_receiver() {
 }
 
 #[test]
-fn charges_fee_when_validate_and_native_is_not_enough() {
+fn open_and_close_account_with_discounted_deposit() {
 	ExtBuilder::default().build().execute_with(|| {
-		// open account for BOB
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
-		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_eq!(Accounts::open_deposit_of(&DAVE), 50);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &DAVE, 200));
+		assert_eq!(Accounts::is_explicit(&DAVE), true);
+		assert_eq!(Accounts::open_deposits(&DAVE), Some(50));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &DAVE), 150);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &DAVE),
+			50
+		);
+
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(ACA, &DAVE, 10));
+		assert_noop!(
+			Accounts::close_account(Origin::signed(DAVE), Some(BOB)),
+			Error::<Runtime>::StillHasActiveReserved,
+		);
+		<Currencies as MultiReservableCurrency<_>>::unreserve(ACA, &DAVE, 10);
+
+		assert_ok!(Accounts::close_account(Origin::signed(DAVE), Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&DAVE), false);
+		assert_eq!(Accounts::open_deposits(&DAVE), None);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &DAVE),
+			0
+		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &ALICE), 100000);
+	});
+}
+
+#[test]
+fn open_and_close_account_deposit_events() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		let account_opened_event = TestEvent::accounts(RawEvent::AccountOpened(BOB));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == account_opened_event));
+
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(CAROL)));
+		let account_closed_event = TestEvent::accounts(RawEvent::AccountClosed(BOB, CAROL));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == account_closed_event));
+	});
+}
+
+#[test]
+fn close_account_to_fresh_recipient() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &DAVE, 60));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &DAVE), 10);
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+
+		// 60 native is not enough to open CAROL
+		assert_noop!(
+			Accounts::close_account(Origin::signed(DAVE), Some(CAROL)),
+			Error::<Runtime>::RecipientCannotReceive,
+		);
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &DAVE, 40));
+		assert_ok!(Accounts::close_account(Origin::signed(DAVE), Some(CAROL)));
+		assert_eq!(Accounts::is_explicit(&DAVE), false);
+		assert_eq!(Accounts::is_explicit(&CAROL), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &CAROL), 0);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &CAROL),
+			100
+		);
+	});
+}
+
+#[test]
+fn close_account_aborts_when_transfer_vetoed() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), Some(DAVE)),
+			Error::<Runtime>::TransferVetoed,
+		);
 		assert_eq!(Accounts::is_explicit(&BOB), true);
-		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 400);
 		assert_eq!(
 			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
 			100
 		);
-		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 1000);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 100);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB), 10);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &DAVE), 0);
 
-		// add liquidity to DEX
+		// BTC is allowed to transfer to CAROL
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(CAROL)));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &CAROL), 10);
+	});
+}
+
+#[test]
+fn close_account_to_currency_work() {
+	ExtBuilder::default().build().execute_with(|| {
 		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
-		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 1000);
 
-		let fee = 500 * 2 + 1000; // len * byte + weight
+		assert_ok!(Accounts::close_account_to_currency(
+			Origin::signed(BOB),
+			AUSD,
+			Some(CAROL)
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(<Currencies as MultiCurrency<_>>::total_balance(ACA, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::total_balance(AUSD, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::total_balance(BTC, &BOB), 0);
+
+		// free native is swapped to AUSD, open deposit is returned in native
 		assert_eq!(
-			ChargeTransactionPayment::<Runtime>::from(0)
-				.validate(&BOB, CALL2, &INFO, 500)
-				.unwrap()
-				.priority,
-			fee
+			<Currencies as MultiCurrency<_>>::free_balance(ACA, &CAROL),
+			NewAccountDeposit::get()
 		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &CAROL), 190);
+		// no liquidity for BTC, transferred as-is
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &CAROL), 10);
 
-		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
-		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
-		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000 - 2000, 1251));
+		assert_noop!(
+			Accounts::close_account_to_currency(Origin::signed(BOB), AUSD, Some(CAROL)),
+			Error::<Runtime>::AccountAlreadyClosed,
+		);
+	});
+}
+
+#[test]
+fn killed_accounts_are_kept_in_recently_killed() {
+	ExtBuilder::default().build().execute_with(|| {
+		for (index, who) in vec![BOB, CAROL, DAVE].into_iter().enumerate() {
+			System::set_block_number(index as u64 + 1);
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &who, 500));
+			assert_ok!(Accounts::close_account(Origin::signed(who), None));
+		}
+
+		// the oldest is evicted beyond capacity
+		assert_eq!(Accounts::recently_killed(), vec![(CAROL, 2), (DAVE, 3)]);
+	});
+}
+
+#[test]
+fn close_accounts_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		for who in vec![BOB, CAROL, DAVE] {
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &who, 500));
+		}
+		// DAVE has active reserved, can not be closed
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(ACA, &DAVE, 10));
+
+		assert_noop!(
+			Accounts::close_accounts(Origin::signed(ALICE), vec![BOB, CAROL], None, false),
+			BadOrigin,
+		);
+		assert_noop!(
+			Accounts::close_accounts(Origin::signed(0), vec![BOB, CAROL, DAVE, ALICE], None, false),
+			Error::<Runtime>::TooManyAccounts,
+		);
+
+		assert_ok!(Accounts::close_accounts(
+			Origin::signed(0),
+			vec![BOB, CAROL, DAVE],
+			Some(ALICE),
+			false
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+		assert_eq!(Accounts::is_explicit(&DAVE), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &ALICE), 99500);
+		let close_account_skipped_event = TestEvent::accounts(RawEvent::CloseAccountSkipped(DAVE));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == close_account_skipped_event));
+	});
+}
+
+#[test]
+fn close_accounts_force_unreserve() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(AUSD, &BOB, 40));
+
+		// skipped without force unreserve
+		assert_ok!(Accounts::close_accounts(
+			Origin::signed(0),
+			vec![BOB],
+			Some(ALICE),
+			false
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+
+		assert_ok!(Accounts::close_accounts(
+			Origin::signed(0),
+			vec![BOB],
+			Some(ALICE),
+			true
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &ALICE), 10000);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(AUSD, &BOB),
+			0
+		);
+		let force_unreserved_event = TestEvent::accounts(RawEvent::ReservedForceUnreserved(BOB, vec![(AUSD, 40)]));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == force_unreserved_event));
+	});
+}
+
+#[test]
+fn consolidate_accounts_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		for who in vec![BOB, CAROL, DAVE] {
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &who, 500));
+			assert_ok!(Accounts::set_consolidation_owner(Origin::signed(who), Some(ALICE)));
+		}
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::consolidation_owner(&BOB), Some(ALICE));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &ALICE), 98500);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &ALICE), 9900);
+
+		// DAVE has active reserved, can not be consolidated
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(ACA, &DAVE, 10));
+
+		assert_noop!(
+			Accounts::consolidate_accounts(Origin::signed(BOB), vec![BOB, CAROL], ALICE),
+			Error::<Runtime>::NotConsolidationOwner,
+		);
+		assert_noop!(
+			Accounts::consolidate_accounts(Origin::signed(ALICE), vec![BOB, CAROL, DAVE, ALICE], ALICE),
+			Error::<Runtime>::TooManySources,
+		);
+		assert_noop!(
+			Accounts::consolidate_accounts(Origin::signed(ALICE), vec![BOB, CAROL], CAROL),
+			Error::<Runtime>::InvalidDestination,
+		);
+
+		assert_ok!(Accounts::consolidate_accounts(
+			Origin::signed(ALICE),
+			vec![BOB, CAROL, DAVE],
+			ALICE
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+		assert_eq!(Accounts::consolidation_owner(&BOB), None);
+		assert_eq!(Accounts::is_explicit(&DAVE), true);
+		assert_eq!(Accounts::consolidation_owner(&DAVE), Some(ALICE));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &DAVE), 440);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &ALICE), 99500);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &ALICE), 10000);
+	});
+}
+
+#[test]
+fn query_max_affordable_fee_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_eq!(Accounts::query_max_affordable_fee(&BOB), 900);
+
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_eq!(Accounts::native_value_of(&BOB, AUSD), 5000);
+		assert_eq!(Accounts::native_value_of(&BOB, BTC), 0);
+		// the supply is capped by `MaxFeeSwapAmount` of 500
+		assert_eq!(Accounts::query_max_affordable_fee(&BOB), 900 + 3333);
+
+		// the locked native is not usable to pay fee
+		PalletBalances::set_lock(*b"1       ", &BOB, 300, WithdrawReasons::all());
+		assert_eq!(Accounts::query_max_affordable_fee(&BOB), 600 + 3333);
+
+		// the allowed locked native pays fee without swap
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 1000));
+		PalletBalances::set_lock(VESTING_LOCK_ID, &CAROL, 400, WithdrawReasons::all());
+		assert_eq!(Accounts::query_max_affordable_fee(&CAROL), 500 + 400);
+	});
+}
+
+#[test]
+fn request_close_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		System::inc_ref(&BOB);
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), Some(ALICE)),
+			Error::<Runtime>::HasConsumers,
+		);
+
+		assert_ok!(Accounts::request_close(Origin::signed(BOB), Some(ALICE)));
+		assert_eq!(
+			Accounts::pending_closes(),
+			vec![(
+				BOB,
+				CloseRequest {
+					recipient: Some(ALICE),
+					expire_at: 11,
+					retry_at: 1,
+					attempts: 0,
+				}
+			)]
+		);
+		assert_eq!(Accounts::close_request_deposits(&BOB), Some(50));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			100 + 50
+		);
+		let close_requested_event = TestEvent::accounts(RawEvent::CloseRequested(BOB, 11));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == close_requested_event));
+		assert_noop!(
+			Accounts::request_close(Origin::signed(BOB), None),
+			Error::<Runtime>::AlreadyRequestedClose,
+		);
+
+		Accounts::on_initialize(2);
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(
+			Accounts::pending_closes(),
+			vec![(
+				BOB,
+				CloseRequest {
+					recipient: Some(ALICE),
+					expire_at: 11,
+					retry_at: 3,
+					attempts: 1,
+				}
+			)]
+		);
+
+		// release the lock, the account is closed with the deposit on next retry
+		System::dec_ref(&BOB);
+		Accounts::on_initialize(3);
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Accounts::pending_closes(), vec![]);
+		assert_eq!(Accounts::close_request_deposits(&BOB), None);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &ALICE), 100000);
+	});
+}
+
+#[test]
+fn request_close_retried_with_backoff_and_budget() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		set_max_closes_per_block(1);
+		for who in vec![BOB, CAROL] {
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &who, 500));
+			System::inc_ref(&who);
+			assert_ok!(Accounts::request_close(Origin::signed(who), None));
+		}
+		let attempts = || {
+			Accounts::pending_closes()
+				.into_iter()
+				.map(|(who, request)| (who, request.attempts, request.retry_at))
+				.collect::<Vec<_>>()
+		};
+
+		// only one request is tried in a block, and moved to the end
+		Accounts::on_initialize(2);
+		assert_eq!(attempts(), vec![(CAROL, 0, 1), (BOB, 1, 3)]);
+		Accounts::on_initialize(3);
+		assert_eq!(attempts(), vec![(BOB, 1, 3), (CAROL, 1, 4)]);
+
+		// the retry interval doubles
+		Accounts::on_initialize(4);
+		assert_eq!(attempts(), vec![(CAROL, 1, 4), (BOB, 2, 6)]);
+		Accounts::on_initialize(5);
+		assert_eq!(attempts(), vec![(BOB, 2, 6), (CAROL, 2, 7)]);
+
+		// not due yet
+		System::dec_ref(&CAROL);
+		Accounts::on_initialize(6);
+		assert_eq!(attempts(), vec![(CAROL, 2, 7), (BOB, 3, 10)]);
+		Accounts::on_initialize(7);
+		assert_eq!(attempts(), vec![(BOB, 3, 10)]);
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+	});
+}
+
+#[test]
+fn request_close_expired() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		for who in vec![BOB, CAROL, DAVE] {
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &who, 500));
+			System::inc_ref(&who);
+		}
+		assert_ok!(Accounts::request_close(Origin::signed(BOB), None));
+		assert_ok!(Accounts::request_close(Origin::signed(CAROL), None));
+		assert_noop!(
+			Accounts::request_close(Origin::signed(DAVE), None),
+			Error::<Runtime>::TooManyPendingCloses,
+		);
+
+		Accounts::on_initialize(11);
+		assert_eq!(Accounts::pending_closes().len(), 2);
+
+		System::dec_ref(&CAROL);
+		Accounts::on_initialize(12);
+		assert_eq!(Accounts::pending_closes(), vec![]);
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+
+		// the deposit is returned on expiry
+		assert_eq!(Accounts::close_request_deposits(&BOB), None);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			100
+		);
+		let close_request_expired_event = TestEvent::accounts(RawEvent::CloseRequestExpired(BOB));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == close_request_expired_event));
+	});
+}
+
+#[test]
+fn charges_fee_from_reserved_non_native() {
+	ExtBuilder::default().build().execute_with(|| {
+		// open account for BOB
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiReservableCurrency<_>>::reserve(AUSD, &BOB, 1000));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 0);
+
+		// add liquidity to DEX
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		let fee = 500 * 2 + 1000; // len * byte + weight
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0)
+				.validate(&BOB, CALL2, &INFO, 500)
+				.unwrap()
+				.priority,
+			fee * 1024 / 1000
+		);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 0);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(AUSD, &BOB),
+			749
+		);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000 - 2000, 1251));
+
+		// the remaining reserved would be below the floor
+		<Currencies as MultiReservableCurrency<_>>::unreserve(AUSD, &BOB, 149);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &BOB, &ALICE, 149));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(AUSD, &BOB),
+			600
+		);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&BOB, CALL2, &INFO, 500)
+			.is_err());
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(AUSD, &BOB),
+			600
+		);
+	});
+}
+
+#[test]
+fn deposit_event_according_to_verbosity() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		let has_accounts_event = || {
+			System::events()
+				.iter()
+				.any(|record| matches!(record.event, TestEvent::accounts(_)))
+		};
+
+		set_event_verbosity(EventLevel::Errors);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(has_accounts_event(), false);
+
+		// the failed fee swap is reported by the validity error, not an event
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0)
+				.pre_dispatch(&CAROL, CALL2, &INFO, 500)
+				.err(),
+			Some(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+		assert_eq!(has_accounts_event(), false);
+
+		set_event_verbosity(EventLevel::Off);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(has_accounts_event(), false);
+
+		set_event_verbosity(EventLevel::Verbose);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		let fee_swapped_event = TestEvent::accounts(RawEvent::FeeSwapped(BOB, AUSD, 418, 2000));
+		assert!(System::events().iter().any(|record| record.event == fee_swapped_event));
+	});
+}
+
+#[test]
+fn set_fee_currency_signed_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let payload = Accounts::fee_currency_payload(&BOB, Some(BTC), 0);
+		assert_noop!(
+			Accounts::set_fee_currency_signed(
+				Origin::signed(ALICE),
+				BOB,
+				Some(BTC),
+				MockSignature(CAROL, payload.clone())
+			),
+			Error::<Runtime>::InvalidSignature,
+		);
+		assert_noop!(
+			Accounts::set_fee_currency_signed(
+				Origin::signed(ALICE),
+				BOB,
+				Some(ACA),
+				MockSignature(BOB, Accounts::fee_currency_payload(&BOB, Some(ACA), 0))
+			),
+			Error::<Runtime>::InvalidFeeCurrency,
+		);
+
+		assert_ok!(Accounts::set_fee_currency_signed(
+			Origin::signed(ALICE),
+			BOB,
+			Some(BTC),
+			MockSignature(BOB, payload.clone())
+		));
+		assert_eq!(Accounts::fee_currency(&BOB), Some(BTC));
+		assert_eq!(Accounts::fee_currency_nonce(&BOB), 1);
+		let fee_currency_updated_event = TestEvent::accounts(RawEvent::FeeCurrencyUpdated(BOB, Some(BTC)));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == fee_currency_updated_event));
+
+		// replayed payload is rejected
+		assert_noop!(
+			Accounts::set_fee_currency_signed(Origin::signed(CAROL), BOB, Some(BTC), MockSignature(BOB, payload)),
+			Error::<Runtime>::InvalidSignature,
+		);
+
+		// the nonce is not used if the fee currency is invalid
+		let payload = Accounts::fee_currency_payload(&BOB, Some(ACA), 1);
+		assert_noop!(
+			Accounts::set_fee_currency_signed(Origin::signed(CAROL), BOB, Some(ACA), MockSignature(BOB, payload)),
+			Error::<Runtime>::InvalidFeeCurrency,
+		);
+		assert_eq!(Accounts::fee_currency_nonce(&BOB), 1);
+
+		// the payload signed for another chain is rejected
+		let payload = Accounts::fee_currency_payload(&BOB, Some(BTC), 1);
+		system::BlockHash::<Runtime>::insert(0, H256::repeat_byte(1));
+		assert_noop!(
+			Accounts::set_fee_currency_signed(Origin::signed(CAROL), BOB, Some(BTC), MockSignature(BOB, payload)),
+			Error::<Runtime>::InvalidSignature,
+		);
+	});
+}
+
+#[test]
+fn set_fee_currency_respects_cooldown() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Accounts::set_fee_currency(Origin::signed(ALICE), Some(BTC)));
+		assert_eq!(Accounts::fee_currency(&ALICE), Some(BTC));
+		assert_eq!(Accounts::fee_currency_changed_at(&ALICE), Some(1));
+
+		System::set_block_number(5);
+		assert_noop!(
+			Accounts::set_fee_currency(Origin::signed(ALICE), Some(AUSD)),
+			Error::<Runtime>::PreferenceChangedTooRecently,
+		);
+
+		System::set_block_number(11);
+		assert_ok!(Accounts::set_fee_currency(Origin::signed(ALICE), Some(AUSD)));
+		assert_eq!(Accounts::fee_currency(&ALICE), Some(AUSD));
+		assert_eq!(Accounts::fee_currency_changed_at(&ALICE), Some(11));
+	});
+}
+
+#[test]
+fn close_account_clears_fee_currency() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_noop!(
+			Accounts::set_fee_currency(Origin::signed(BOB), Some(ACA)),
+			Error::<Runtime>::InvalidFeeCurrency,
+		);
+		assert_ok!(Accounts::set_fee_currency(Origin::signed(BOB), Some(AUSD)));
+		assert_eq!(Accounts::fee_currency(&BOB), Some(AUSD));
+
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(CAROL)));
+		assert_eq!(Accounts::fee_currency(&BOB), None);
+		assert_eq!(Accounts::fee_currency_changed_at(&BOB), None);
+	});
+}
+
+#[test]
+fn charges_fee_by_preferred_fee_currency() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), BTC, AUSD, 100, 1000));
+		assert_ok!(Accounts::set_fee_currency_signed(
+			Origin::signed(ALICE),
+			BOB,
+			Some(BTC),
+			MockSignature(BOB, Accounts::fee_currency_payload(&BOB, Some(BTC), 0))
+		));
+
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+		assert!(Currencies::free_balance(BTC, &BOB) < 100);
+	});
+}
+
+#[test]
+fn query_fee_in_currency_include_swap_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		let native_fee = Accounts::query_native_fee(10, &INFO, false);
+		let swap_inclusive_fee = Accounts::query_native_fee(10, &INFO, true);
+		assert_eq!(native_fee, 1020);
+		assert_eq!(
+			swap_inclusive_fee,
+			native_fee + <Runtime as Trait>::WeightInfo::swap_fee() as Balance
+		);
+		assert_eq!(Accounts::query_fee_in_currency(10, &INFO, ACA), Some(native_fee));
+
+		// no liquidity for AUSD/ACA
+		assert_eq!(Accounts::query_fee_in_currency(10, &INFO, AUSD), None);
+	});
+}
+
+#[test]
+fn priority_distinguishes_slight_weight_difference() {
+	ExtBuilder::default().build().execute_with(|| {
+		let info = DispatchInfo {
+			weight: 1000,
+			class: DispatchClass::Normal,
+			pays_fee: Pays::Yes,
+		};
+		let heavier_info = DispatchInfo { weight: 1010, ..info };
+		let fee = 1_000_000u128;
+
+		// integer division collapses both coefficients to 1
+		assert_eq!(1024 / info.weight, 1024 / heavier_info.weight);
+
+		let priority = ChargeTransactionPayment::<Runtime>::get_priority(23, &info, fee);
+		let heavier_priority = ChargeTransactionPayment::<Runtime>::get_priority(23, &heavier_info, fee);
+		assert_eq!(priority, 1_024_000);
+		assert_eq!(heavier_priority, 1_013_861);
+		assert!(priority > heavier_priority);
+	});
+}
+
+#[test]
+fn zero_fee_get_baseline_priority() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		let free_info = DispatchInfo {
+			weight: 1000,
+			class: DispatchClass::Normal,
+			pays_fee: Pays::No,
+		};
+
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0)
+				.validate(&BOB, CALL2, &free_info, 500)
+				.unwrap()
+				.priority,
+			ZeroFeePriority::get()
+		);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+	});
+}
+
+#[test]
+fn charges_fee_by_cached_fee_path() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), BTC, AUSD, 100, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, BTC, 10000, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 100));
+
+		assert_noop!(
+			Accounts::cache_fee_path(Origin::signed(BOB), ACA),
+			Error::<Runtime>::InvalidFeeCurrency,
+		);
+		assert_ok!(Accounts::cache_fee_path(Origin::signed(BOB), BTC));
+		assert_eq!(Accounts::cached_fee_path(&BOB, BTC), Some((vec![BTC, ACA], 1)));
+
+		// cached path is used
+		let direct_pool = DEXModule::liquidity_pool(TradingPair::new(ACA, BTC));
+		let stable_pool = DEXModule::liquidity_pool(TradingPair::new(AUSD, BTC));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_ne!(DEXModule::liquidity_pool(TradingPair::new(ACA, BTC)), direct_pool);
+		assert_eq!(DEXModule::liquidity_pool(TradingPair::new(AUSD, BTC)), stable_pool);
+
+		// stale path is recomputed
+		System::set_block_number(11);
+		let direct_pool = DEXModule::liquidity_pool(TradingPair::new(ACA, BTC));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(DEXModule::liquidity_pool(TradingPair::new(ACA, BTC)), direct_pool);
+		assert_ne!(DEXModule::liquidity_pool(TradingPair::new(AUSD, BTC)), stable_pool);
+	});
+}
+
+#[test]
+fn charges_fee_according_to_call_fee_policy() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_fee_decision(Some(FeeDecision::Free));
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0)
+				.validate(&ALICE, CALL, &INFO, 23)
+				.unwrap()
+				.priority,
+			ZeroFeePriority::get()
+		);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000);
+
+		set_fee_decision(Some(FeeDecision::Reject));
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&ALICE, CALL, &INFO, 23),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Call))
+		);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000);
+
+		set_fee_decision(Some(FeeDecision::Charge(50)));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 50);
+
+		set_fee_decision(None);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 50 - 1046);
+	});
+}
+
+#[test]
+fn fee_free_calls_pay_no_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let call_index = CALL.using_encoded(Accounts::call_index_of).unwrap();
+		assert_noop!(
+			Accounts::set_fee_free_call(Origin::signed(ALICE), call_index, true),
+			BadOrigin
+		);
+		assert_ok!(Accounts::set_fee_free_call(Origin::signed(0), call_index, true));
+		assert_eq!(Accounts::fee_free_calls(call_index), true);
+		let fee_free_call_updated_event = TestEvent::accounts(RawEvent::FeeFreeCallUpdated(call_index, true));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == fee_free_call_updated_event));
+
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0)
+				.validate(&ALICE, CALL, &INFO, 23)
+				.unwrap()
+				.priority,
+			ZeroFeePriority::get()
+		);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000);
+
+		// other calls still pay fee
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&ALICE, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 1046);
+
+		assert_ok!(Accounts::set_fee_free_call(Origin::signed(0), call_index, false));
+		assert_eq!(Accounts::fee_free_calls(call_index), false);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 1046 * 2);
+	});
+}
+
+#[test]
+fn only_swap_fee_gap_when_native_is_partially_enough() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 600));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &CAROL, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 500);
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 0);
+
+		// only the gap 1500 is swapped
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000 - 177);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000 - 1500, 1177));
+
+		// the withdrawn native is put back if the residual fee can not be swapped
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 5000)
+			.is_err());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 500);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000 - 177);
+
+		// the whole fee is swapped if there's no native
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&CAROL, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &CAROL), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &CAROL), 1000 - 363);
+		assert_eq!(
+			DEXModule::get_liquidity_pool(ACA, AUSD),
+			(10000 - 1500 - 2000, 1177 + 363)
+		);
+	});
+}
+
+#[test]
+fn charges_fee_from_approved_fee_payer() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::with_fee_payer(0, ALICE).validate(&BOB, CALL, &INFO, 23),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof))
+		);
+
+		assert_ok!(Accounts::approve_fee_payer(Origin::signed(ALICE), BOB, true));
+		assert_eq!(Accounts::fee_payer_approvals(ALICE, BOB), true);
+		let fee = 23 * 2 + 1000; // len * byte + weight
+		let pre = ChargeTransactionPayment::<Runtime>::with_fee_payer(0, ALICE)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.unwrap();
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - fee);
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 23, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - fee + 200);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+
+		assert_ok!(Accounts::approve_fee_payer(Origin::signed(ALICE), BOB, false));
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::with_fee_payer(0, ALICE).validate(&BOB, CALL, &INFO, 23),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof))
+		);
+	});
+}
+
+#[test]
+fn charges_non_native_tip() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		let fee = 23 * 2 + 1000; // len * byte + weight
+
+		// 10 AUSD is swapped to 99 ACA as tip
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::with_non_native_tip(0, AUSD, 10)
+				.validate(&ALICE, CALL, &INFO, 23)
+				.unwrap()
+				.priority,
+			(fee + 99) * 1024 / 1000
+		);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 90000 + 99 - (fee + 99));
+		assert_eq!(Currencies::free_balance(AUSD, &ALICE), 9000 - 10);
+
+		// unused fee is refunded but the tip is not
+		let pre = ChargeTransactionPayment::<Runtime>::with_non_native_tip(0, AUSD, 10)
+			.pre_dispatch(&ALICE, CALL, &INFO, 23)
+			.unwrap();
+		assert_eq!(pre.0, 97);
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 23, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 90000 - fee - fee + 200);
+		assert_eq!(Currencies::free_balance(AUSD, &ALICE), 9000 - 20);
+	});
+}
+
+#[test]
+fn non_native_tip_is_not_swapped_when_no_fee_is_paid() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// free by the fee policy
+		set_fee_decision(Some(FeeDecision::Free));
+		assert!(ChargeTransactionPayment::<Runtime>::with_non_native_tip(0, AUSD, 10)
+			.pre_dispatch(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 90000);
+		assert_eq!(Currencies::free_balance(AUSD, &ALICE), 9000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+		set_fee_decision(None);
+
+		// under the free quota
+		set_free_quota(1);
+		assert!(ChargeTransactionPayment::<Runtime>::with_non_native_tip(0, AUSD, 10)
+			.pre_dispatch(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 90000);
+		assert_eq!(Currencies::free_balance(AUSD, &ALICE), 9000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		// the tip is swapped once the quota is used up
+		assert!(ChargeTransactionPayment::<Runtime>::with_non_native_tip(0, AUSD, 10)
+			.pre_dispatch(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &ALICE), 9000 - 10);
+	});
+}
+
+#[test]
+fn fee_swap_weight_is_checked_by_check_weight() {
+	ExtBuilder::default().build().execute_with(|| {
+		// paid by native, only the weight of the call is counted
+		assert!((
+			ChargeTransactionPayment::<Runtime>::from(0),
+			system::CheckWeight::<Runtime>::new()
+		)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 500)
+			.is_ok());
+	});
+
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// the weight of the fee swap exceeds the block weight limit
+		assert_eq!(
+			(
+				ChargeTransactionPayment::<Runtime>::from(0),
+				system::CheckWeight::<Runtime>::new()
+			)
+				.pre_dispatch(&BOB, CALL2, &INFO, 500)
+				.err(),
+			Some(InvalidTransaction::ExhaustsResources.into())
+		);
+	});
+}
+
+#[test]
+fn refund_in_currency_swapped_to_pay_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// paid by native, refund in native
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 500)
+			.unwrap();
+		assert_eq!(pre.4, None);
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 90000 - 2000 + 200);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		// paid by AUSD, refund is swapped back to AUSD
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.unwrap();
+		assert_eq!(pre.4, Some((AUSD, 251, 2000)));
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (8000, 1251));
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749 + 30);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (8200, 1221));
+	});
+}
+
+#[test]
+fn refund_in_native_beyond_swapped_part_of_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 2000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// only the gap of 100 native is paid by AUSD
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.unwrap();
+		assert_eq!(pre.4, Some((AUSD, 11, 100)));
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 989);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (9900, 1011));
+
+		// the refund of 200 is swapped back up to the swapped 100 native, the rest is
+		// kept in native
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 100);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 989 + 10);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1001));
+	});
+}
+
+#[test]
+fn fee_swap_never_spends_beyond_cap() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 3000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 3000, 1000));
+
+		// swapping 2000 native requires 2001 AUSD, beyond the cap 500
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_err());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 3000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (3000, 1000));
+
+		// move on to BTC which requires 26 BTC
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 50));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, BTC, 10000, 100));
+		assert_ok!(Accounts::cache_fee_path(Origin::signed(BOB), BTC));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 3000);
+		assert_eq!(Currencies::free_balance(BTC, &BOB), 50 - 26);
+	});
+}
+
+#[test]
+fn no_auto_swap_when_disabled() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		assert_ok!(Accounts::set_auto_swap(Origin::signed(BOB), false));
+		assert_eq!(Accounts::no_auto_swap(&BOB), true);
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&BOB, CALL2, &INFO, 500),
+			Err(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		assert_ok!(Accounts::set_auto_swap(Origin::signed(BOB), true));
+		assert_eq!(Accounts::no_auto_swap(&BOB), false);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
+
+		// reset when account is closed
+		assert_ok!(Accounts::set_auto_swap(Origin::signed(BOB), false));
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(CAROL)));
+		assert_eq!(Accounts::no_auto_swap(&BOB), false);
+	});
+}
+
+#[test]
+fn charges_fee_when_validate_and_native_is_not_enough() {
+	ExtBuilder::default().build().execute_with(|| {
+		// open account for BOB
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 0);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			100
+		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 1000);
+
+		// add liquidity to DEX
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		let fee = 500 * 2 + 1000; // len * byte + weight
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0)
+				.validate(&BOB, CALL2, &INFO, 500)
+				.unwrap()
+				.priority,
+			fee * 1024 / 1000
+		);
+
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000 - 2000, 1251));
+	});
+}
+
+#[test]
+fn charges_fee_under_currency_slippage_limit() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_eq!(Accounts::slippage_limit_of(AUSD), Ratio::one());
+
+		let slippage = Ratio::saturating_from_rational(1, 10);
+		assert_noop!(
+			Accounts::set_currency_slippage(Origin::signed(ALICE), AUSD, Some(slippage)),
+			BadOrigin
+		);
+		assert_ok!(Accounts::set_currency_slippage(Origin::signed(0), AUSD, Some(slippage)));
+		assert_eq!(Accounts::slippage_limit_of(AUSD), slippage);
+		assert_eq!(Accounts::slippage_limit_of(BTC), Ratio::one());
+		let currency_slippage_updated_event =
+			TestEvent::accounts(RawEvent::CurrencySlippageUpdated(AUSD, Some(slippage)));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == currency_slippage_updated_event));
+
+		// swapping 2000 native exceeds the 10% price impact limit of AUSD
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&BOB, CALL2, &INFO, 500)
+			.is_err());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+
+		assert_ok!(Accounts::set_currency_slippage(Origin::signed(0), AUSD, None));
+		assert_eq!(Accounts::currency_slippage(AUSD), None);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&BOB, CALL2, &INFO, 500)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
+	});
+}
+
+#[test]
+fn ensure_can_charge_fee_swaps_the_gap() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 600));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		let reason = WithdrawReason::TransactionPayment.into();
+
+		// native is enough
+		assert_ok!(Accounts::ensure_can_charge_fee(&BOB, 500, reason));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 500);
+
+		assert_eq!(
+			Accounts::ensure_can_charge_fee(&BOB, 600, reason),
+			Err(Error::<Runtime>::NotEnoughBalance.into())
+		);
+
+		// swap the gap of 100 native with AUSD
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_ok!(Accounts::ensure_can_charge_fee(&BOB, 600, reason));
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 600);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 89);
+	});
+}
+
+#[test]
+fn charge_evm_fee_with_non_native() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 89);
+
+		let imbalance = Accounts::charge_evm_fee(&BOB, 200).unwrap();
+		assert_eq!(imbalance.peek(), 200);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 68);
+
+		assert_eq!(
+			Accounts::charge_evm_fee(&BOB, 2000).map(|imbalance| imbalance.peek()),
+			Err(Error::<Runtime>::NotEnoughBalance.into())
+		);
+	});
+}
+
+#[test]
+fn fee_relief_fund_covers_shortfall_of_relief_calls() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let fund = Accounts::fee_relief_account_id();
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &fund, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 700));
+		assert_eq!(Currencies::free_balance(ACA, &fund), 900);
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 600);
+
+		// not a relief call
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&BOB, CALL, &INFO, 23),
+			Err(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+
+		let call_index = CALL.using_encoded(Accounts::call_index_of).unwrap();
+		assert_noop!(
+			Accounts::set_fee_relief_call(Origin::signed(ALICE), call_index, true),
+			BadOrigin
+		);
+		assert_ok!(Accounts::set_fee_relief_call(Origin::signed(0), call_index, true));
+		assert_eq!(Accounts::fee_relief_calls(call_index), true);
+		let fee_relief_call_updated_event = TestEvent::accounts(RawEvent::FeeReliefCallUpdated(call_index, true));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == fee_relief_call_updated_event));
+
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.validate(&BOB, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 0);
+		assert_eq!(Currencies::free_balance(ACA, &fund), 900 - 446);
+		assert_eq!(Accounts::fee_relief_debts(&BOB), 446);
+		let fee_relieved_event = TestEvent::accounts(RawEvent::FeeRelieved(BOB, 446));
+		assert!(System::events().iter().any(|record| record.event == fee_relieved_event));
+
+		// the debt can not exceed `MaxFeeReliefDebt`
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&BOB, CALL, &INFO, 23),
+			Err(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+		assert_eq!(Accounts::fee_relief_debts(&BOB), 446);
+
+		// the debt is repaid once the account has native currency again
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 2000));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 2000 - 1046 - 446);
+		assert_eq!(Currencies::free_balance(ACA, &fund), 900);
+		assert_eq!(FeeReliefDebts::<Runtime>::contains_key(&BOB), false);
+		let fee_relief_repaid_event = TestEvent::accounts(RawEvent::FeeReliefRepaid(BOB, 446));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == fee_relief_repaid_event));
+	});
+}
+
+#[test]
+fn fee_relief_debt_is_settled_when_close_account() {
+	ExtBuilder::default().build().execute_with(|| {
+		let fund = Accounts::fee_relief_account_id();
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &fund, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 700));
+		let call_index = CALL.using_encoded(Accounts::call_index_of).unwrap();
+		assert_ok!(Accounts::set_fee_relief_call(Origin::signed(0), call_index, true));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Accounts::fee_relief_debts(&BOB), 446);
+
+		// the open deposit is not enough to repay the debt
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), Some(ALICE)),
+			Error::<Runtime>::FeeReliefDebtOutstanding,
+		);
+
+		// the debt is repaid from the swept native currency
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 400));
+		let alice_balance = Currencies::free_balance(ACA, &ALICE);
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(Currencies::free_balance(ACA, &fund), 900);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), alice_balance + 500 - 446);
+		assert_eq!(FeeReliefDebts::<Runtime>::contains_key(&BOB), false);
+	});
+}
+
+#[test]
+fn fee_relief_debt_is_kept_when_account_is_reaped() {
+	ExtBuilder::default().build().execute_with(|| {
+		FeeReliefDebts::<Runtime>::insert(&BOB, 446);
+		Accounts::on_killed_account(&BOB);
+		assert_eq!(Accounts::fee_relief_debts(&BOB), 446);
+	});
+}
+
+#[test]
+fn refund_in_native_when_swap_back_rate_is_worse_than_paid() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.unwrap();
+		assert_eq!(pre.4, Some((AUSD, 251, 2000)));
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
+		assert_ok!(Accounts::set_currency_slippage(
+			Origin::signed(0),
+			AUSD,
+			Some(Ratio::saturating_from_rational(10, 100))
+		));
+
+		// adverse price move between charge and refund
+		assert_ok!(DEXModule::swap_with_exact_supply(
+			Origin::signed(ALICE),
+			vec![ACA, AUSD],
+			8000,
+			0
+		));
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (16000, 626));
+
+		// swapping back 200 native gets only 7 AUSD, worse than the paid rate beyond
+		// the slippage limit, refund in native
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 200);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 749);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (16000, 626));
+	});
+}
+
+#[test]
+fn sweep_currencies_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+
+		assert_noop!(
+			Accounts::sweep_currencies(Origin::signed(BOB), vec![BTC, ACA], ALICE),
+			Error::<Runtime>::CannotSweepNativeCurrency,
+		);
+
+		assert_ok!(Accounts::sweep_currencies(Origin::signed(BOB), vec![BTC], ALICE));
+		let currencies_swept_event = TestEvent::accounts(RawEvent::CurrenciesSwept(BOB, ALICE, vec![BTC]));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == currencies_swept_event));
+
+		// the account is kept open with other balances and the deposit
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB), 0);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &ALICE), 1000);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), 100);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &BOB), 400);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &BOB),
+			100
+		);
+	});
+}
+
+#[test]
+fn register_weight_of_currencies_tried_to_pay_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), BTC, AUSD, 100, 1000));
+
+		// native currency is enough, no currency is tried
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(System::block_weight().get(DispatchClass::Normal), 0);
+
+		// AUSD without balance is skipped, only BTC is tried
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(
+			System::block_weight().get(DispatchClass::Normal),
+			<() as WeightInfo>::withdraw_fee(1)
+		);
+	});
+}
+
+#[test]
+fn fee_currency_order_follows_strategy() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), BTC, AUSD, 10, 200));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 50));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+
+		assert_eq!(Accounts::fee_currency_order(&BOB), vec![AUSD, BTC]);
+
+		set_fee_order(MockFeeOrder::ByUserBalance);
+		assert_eq!(Accounts::fee_currency_order(&BOB), vec![AUSD, BTC]);
+
+		// the stable side of BTC-AUSD pool is deeper than ACA-AUSD pool
+		set_fee_order(MockFeeOrder::ByDexLiquidity);
+		assert_eq!(Accounts::fee_currency_order(&BOB), vec![BTC, AUSD]);
+
+		// the preferred fee currency is always the first
+		assert_ok!(Accounts::set_fee_currency(Origin::signed(BOB), Some(AUSD)));
+		assert_eq!(Accounts::fee_currency_order(&BOB), vec![AUSD, BTC]);
+	});
+}
+
+#[test]
+fn distinct_error_when_swapped_fee_is_still_not_withdrawable() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		PalletBalances::set_lock(*b"1       ", &BOB, 2000, WithdrawReasons::all());
+
+		// no currency can cover the fee
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&BOB, CALL2, &INFO, 23),
+			Err(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+
+		// the swap succeeds, but the lock still blocks withdrawing the fee
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&BOB, CALL2, &INFO, 23),
+			Err(InvalidTransaction::Custom(FEE_WITHDRAW_BLOCKED).into())
+		);
+	});
+}
+
+#[test]
+fn fee_swap_is_reverted_when_withdraw_fails() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		PalletBalances::set_lock(*b"1       ", &BOB, 2000, WithdrawReasons::all());
+
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_err());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 900);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+		assert_eq!(Accounts::fees_collected(AUSD), 0);
+
+		// the swap is kept once the fee is withdrawn
+		PalletBalances::set_lock(*b"1       ", &BOB, 900, WithdrawReasons::all());
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 900);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 883);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (8954, 1117));
+	});
+}
+
+#[test]
+fn evm_fee_swap_is_reverted_when_withdraw_fails() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		PalletBalances::set_lock(*b"1       ", &BOB, 2000, WithdrawReasons::all());
+
+		assert!(Accounts::charge_evm_fee(&BOB, 1046).is_err());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 900);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+	});
+}
+
+#[test]
+fn close_accounts_to_module_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let module_id = ModuleId(*b"aca/bybk");
+		let module_account: AccountId = module_id.into_account();
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(
+			ACA,
+			&ALICE,
+			&module_account,
+			1000
+		));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+
+		assert_noop!(
+			Accounts::close_accounts_to_module(Origin::signed(ALICE), vec![BOB], module_id, false),
+			BadOrigin,
+		);
+
+		assert_ok!(Accounts::close_accounts_to_module(
+			Origin::signed(0),
+			vec![BOB],
+			module_id,
+			false
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(
+			<Currencies as MultiCurrency<_>>::free_balance(ACA, &module_account),
+			1400
+		);
+		assert_eq!(
+			<Currencies as MultiCurrency<_>>::free_balance(AUSD, &module_account),
+			100
+		);
+
+		// the closed account is skipped
+		assert_ok!(Accounts::close_accounts_to_module(
+			Origin::signed(0),
+			vec![BOB],
+			module_id,
+			false
+		));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(
+			<Currencies as MultiCurrency<_>>::free_balance(ACA, &module_account),
+			1400
+		);
+		let close_account_skipped_event = TestEvent::accounts(RawEvent::CloseAccountSkipped(BOB));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == close_account_skipped_event));
+	});
+}
+
+#[test]
+fn fees_collected_by_currency_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// the actual fee is counted after refund, excluding the tip
+		let pre = ChargeTransactionPayment::<Runtime>::from(100)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 500)
+			.unwrap();
+		assert_eq!(Accounts::fees_collected(ACA), 0);
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		assert_eq!(Accounts::fees_collected(ACA), 1800);
+		assert_eq!(Accounts::fees_collected(AUSD), 0);
+
+		// counted in AUSD before swapping, excluding the part swapped back
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.unwrap();
+		assert_eq!(pre.4, Some((AUSD, 251, 2000)));
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		assert_eq!(Accounts::fees_collected(ACA), 1800);
+		assert_eq!(Accounts::fees_collected(AUSD), 251 - 30);
+
+		assert_noop!(Accounts::reset_fees_collected(Origin::signed(ALICE)), BadOrigin);
+		assert_ok!(Accounts::reset_fees_collected(Origin::signed(0)));
+		assert_eq!(Accounts::fees_collected(ACA), 0);
+		assert_eq!(Accounts::fees_collected(AUSD), 0);
+	});
+}
+
+#[test]
+fn close_account_to_new_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &DAVE, 90));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &DAVE, 100));
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+
+		// 90 native is not enough to open CAROL with deposit
+		assert_noop!(
+			Accounts::close_account_to_new(Origin::signed(DAVE), CAROL),
+			Error::<Runtime>::RecipientCannotReceive,
+		);
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &DAVE, 10));
+		assert_ok!(Accounts::close_account_to_new(Origin::signed(DAVE), CAROL));
+		assert_eq!(Accounts::is_explicit(&DAVE), false);
+		assert_eq!(Accounts::is_explicit(&CAROL), true);
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &CAROL),
+			100
+		);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &CAROL), 100);
+
+		assert_noop!(
+			Accounts::close_account_to_new(Origin::signed(DAVE), BOB),
+			Error::<Runtime>::AccountAlreadyClosed,
+		);
+	});
+}
+
+#[test]
+fn reject_transaction_exceeding_max_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// the fee is 1046, rejected without swapping
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::with_max_fee(0, 1045).validate(&BOB, CALL2, &INFO, 23),
+			Err(InvalidTransaction::Payment.into())
+		);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+
+		assert!(ChargeTransactionPayment::<Runtime>::with_max_fee(0, 1046)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 883);
+	});
+}
+
+#[test]
+fn receiving_into_excluded_accounts_does_not_swap() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		let treasury = Accounts::treasury_account_id();
+		let fee_relief_fund = Accounts::fee_relief_account_id();
+
+		for who in vec![treasury, fee_relief_fund] {
+			assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &who, 100));
+			assert_eq!(Accounts::is_explicit(&who), false);
+			assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &who), 100);
+			assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &who), 0);
+		}
+
+		// other accounts are opened by swapping
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &CAROL, 100));
+		assert_eq!(Accounts::is_explicit(&CAROL), true);
+	});
+}
+
+#[test]
+fn fee_charged_event_records_final_fee() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		let call_index = CALL2.using_encoded(Accounts::call_index_of).unwrap();
+
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 500)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		let fee_charged_event = TestEvent::accounts(RawEvent::FeeCharged(ALICE, 1, call_index, 1800, ACA, 1800));
+		assert!(System::events().iter().any(|record| record.event == fee_charged_event));
+
+		// 251 AUSD is swapped to pay 2000 fee, and the refund of 200 is swapped back to 30 AUSD
+		System::set_block_number(2);
+		let pre = ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 500)
+			.unwrap();
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 500, &Ok(())).is_ok());
+		let fee_charged_event = TestEvent::accounts(RawEvent::FeeCharged(BOB, 2, call_index, 1800, AUSD, 221));
+		assert!(System::events().iter().any(|record| record.event == fee_charged_event));
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 779);
+	});
+}
+
+#[test]
+fn force_close_account_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 100));
+		System::inc_ref(&BOB);
+		assert_noop!(
+			Accounts::close_account(Origin::signed(BOB), Some(ALICE)),
+			Error::<Runtime>::HasConsumers,
+		);
+
+		assert_noop!(
+			Accounts::force_close_account(Origin::signed(0), BOB, Some(ALICE)),
+			BadOrigin,
+		);
+
+		set_account_references(vec![], vec![BOB]);
+		assert_noop!(
+			Accounts::force_close_account(Origin::root(), BOB, Some(ALICE)),
+			Error::<Runtime>::HasProviders,
+		);
+
+		set_account_references(vec![], vec![]);
+		assert_ok!(Accounts::force_close_account(Origin::root(), BOB, Some(ALICE)));
+		assert_eq!(Accounts::is_explicit(&BOB), false);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(ACA, &ALICE), 100000);
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &ALICE), 10000);
+		let account_force_closed_event = TestEvent::accounts(RawEvent::AccountForceClosed(BOB, ALICE));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == account_force_closed_event));
+
+		assert_noop!(
+			Accounts::force_close_account(Origin::root(), BOB, Some(ALICE)),
+			Error::<Runtime>::AccountAlreadyClosed,
+		);
+	});
+}
+
+#[test]
+fn free_quota_per_era_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_free_quota(3);
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 10000));
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 9900);
+
+		for _ in 0..3 {
+			assert!(ChargeTransactionPayment::<Runtime>::from(0)
+				.pre_dispatch(&BOB, CALL2, &INFO, 23)
+				.is_ok());
+		}
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 9900);
+		assert_eq!(Accounts::free_quota_used(BOB), (0, 3));
+
+		// the 4th transaction in the era is charged
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 8854);
+
+		// the quota resets in the next era
+		set_era(1);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &BOB), 8854);
+		assert_eq!(Accounts::free_quota_used(BOB), (1, 1));
+		assert_eq!(Accounts::free_quota_used_in_era(&BOB), 1);
+
+		// the quota is cleared once the account is killed
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), Some(ALICE)));
+		assert_eq!(FreeQuotaUsed::<Runtime>::contains_key(BOB), false);
+	});
+}
+
+#[test]
+fn no_free_quota_for_unopened_account() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_free_quota(3);
+		assert_eq!(Accounts::is_explicit(&DAVE), false);
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&DAVE, CALL2, &INFO, 23),
+			Err(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+		assert_eq!(FreeQuotaUsed::<Runtime>::contains_key(DAVE), false);
+		assert_eq!(Accounts::simulate_fee(&DAVE, CALL2, &INFO, 23).0, 1046);
+	});
+}
+
+#[test]
+fn simulate_fee_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		assert_eq!(Accounts::simulate_fee(&ALICE, CALL2, &INFO, 23), (1046, Some(ACA)));
+		assert_eq!(Accounts::simulate_fee(&BOB, CALL2, &INFO, 23), (1046, Some(AUSD)));
+		assert_eq!(Accounts::simulate_fee(&CAROL, CALL2, &INFO, 23), (1046, None));
+
+		// nothing is swapped
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), (10000, 1000));
+
+		set_fee_decision(Some(FeeDecision::Free));
+		assert_eq!(Accounts::simulate_fee(&BOB, CALL2, &INFO, 23), (0, None));
+	});
+}
+
+#[test]
+fn skip_currency_below_min_swap_amount() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// 117 AUSD is required to swap the fee
+		set_min_swap_amounts(vec![(AUSD, 118)]);
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from(0).validate(&BOB, CALL2, &INFO, 23),
+			Err(InvalidTransaction::Custom(FEE_SWAP_FAILED).into())
+		);
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 1000);
+
+		set_min_swap_amounts(vec![(AUSD, 117)]);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), 883);
+	});
+}
+
+#[test]
+#[should_panic(expected = "initial account must be funded to be opened")]
+fn unfunded_initial_account_fails_genesis() {
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Runtime>()
+		.unwrap();
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(ALICE, 1000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let _ = GenesisConfig::<Runtime> {
+		initial_accounts: vec![(ALICE, None), (CAROL, Some(AUSD))],
+	}
+	.assimilate_storage(&mut t);
+}
+
+#[test]
+fn open_initial_accounts_at_genesis() {
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Runtime>()
+		.unwrap();
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(ALICE, 1000), (BOB, 1000)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	GenesisConfig::<Runtime> {
+		initial_accounts: vec![(ALICE, Some(AUSD)), (BOB, None)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		// the deposit is reserved only once
+		for who in vec![ALICE, BOB] {
+			assert_eq!(Accounts::is_explicit(&who), true);
+			assert_eq!(Accounts::open_deposits(&who), Some(100));
+			assert_eq!(
+				<Currencies as MultiReservableCurrency<_>>::reserved_balance(ACA, &who),
+				100
+			);
+		}
+		assert_eq!(Accounts::fee_currency(&ALICE), Some(AUSD));
+		assert_eq!(Accounts::fee_currency(&BOB), None);
+	});
+}
+
+#[test]
+fn notify_kill_account_with_reason() {
+	ExtBuilder::default().build().execute_with(|| {
+		// 50 native is dust, swept to treasury
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 50));
+		assert_eq!(Accounts::is_explicit(&CAROL), false);
+		assert_eq!(killed_accounts(), vec![(CAROL, KillReason::DustSwept)]);
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 500));
+		assert_ok!(Accounts::close_account(Origin::signed(BOB), None));
+		assert_eq!(
+			killed_accounts(),
+			vec![(CAROL, KillReason::DustSwept), (BOB, KillReason::Closed)]
+		);
+	});
+}
+
+#[test]
+fn fee_swap_path_is_limited_by_max_hops() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Accounts::fee_swap_path(BTC).len() as u32 - 1, MaxFeeSwapHops::get());
+
+		assert_noop!(
+			Accounts::set_fee_swap_path(Origin::signed(0), BTC, Some(vec![BTC, AUSD, BTC, ACA])),
+			Error::<Runtime>::PathTooLong,
+		);
+		assert_ok!(Accounts::set_fee_swap_path(
+			Origin::signed(0),
+			BTC,
+			Some(vec![BTC, AUSD, ACA])
+		));
+		assert_eq!(Accounts::fee_swap_path(BTC), vec![BTC, AUSD, ACA]);
+	});
+}
+
+#[test]
+fn payable_fee_currencies_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1100));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 100));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+
+		// BTC has no liquidity
+		assert_eq!(Accounts::payable_fee_currencies(&BOB, 1000), vec![ACA, AUSD]);
+
+		// 1001 native is beyond the usable, 112 AUSD is required
+		assert_eq!(Accounts::payable_fee_currencies(&BOB, 1001), vec![AUSD]);
+
+		// 5000 native requires 1001 AUSD, beyond `MaxFeeSwapAmount`
+		assert_eq!(Accounts::payable_fee_currencies(&BOB, 5000), vec![]);
+		assert_eq!(Accounts::payable_fee_currencies(&CAROL, 1000), vec![]);
+	});
+}
+
+#[test]
+fn route_through_secondary_stable_currency_when_primary_pool_is_dry() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			Accounts::fee_swap_paths(BTC),
+			vec![vec![BTC, AUSD, ACA], vec![BTC, DOT, ACA]]
+		);
+
+		// the BTC-AUSD pool is dry
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, DOT, 10000, 1000));
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), DOT, BTC, 1000, 100));
+
+		// opened by swapping the received BTC through DOT
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 100));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+		assert_eq!(opened_via_swap(), vec![(BOB, BTC)]);
+		assert_eq!(DEXModule::get_liquidity_pool(BTC, AUSD), (0, 0));
+		let btc_balance = <Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB);
+		assert!(btc_balance < 100);
+
+		// fee is paid by swapping BTC through DOT
+		let dot_pool = DEXModule::get_liquidity_pool(ACA, DOT);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(DEXModule::get_liquidity_pool(BTC, AUSD), (0, 0));
+		assert_ne!(DEXModule::get_liquidity_pool(ACA, DOT), dot_pool);
+		assert!(<Currencies as MultiCurrency<_>>::free_balance(BTC, &BOB) < btc_balance);
+	});
+}
+
+#[test]
+fn charge_multisig_approval_to_registered_payer() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 2000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 2000));
+
+		// CAROL is the multisig, ALICE pays for it after approved
+		assert_noop!(
+			Accounts::set_multisig_fee_payer(Origin::signed(CAROL), Some(ALICE)),
+			Error::<Runtime>::FeePayerNotApproved,
+		);
+		assert_ok!(Accounts::approve_fee_payer(Origin::signed(ALICE), CAROL, true));
+		assert_ok!(Accounts::set_multisig_fee_payer(Origin::signed(CAROL), Some(ALICE)));
+		assert_eq!(Accounts::multisig_fee_payer(&CAROL), Some(ALICE));
+
+		// not a multisig approval, the approver pays
+		let alice_balance = PalletBalances::free_balance(ALICE);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(PalletBalances::free_balance(BOB), 2000 - 100 - 1046);
+		assert_eq!(PalletBalances::free_balance(ALICE), alice_balance);
+
+		// the multisig approval is paid by ALICE
+		set_multisig(Some(CAROL));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(PalletBalances::free_balance(BOB), 2000 - 100 - 1046);
+		assert_eq!(PalletBalances::free_balance(ALICE), alice_balance - 1046);
+
+		// the approver pays again once ALICE revokes the approval
+		assert_ok!(Accounts::approve_fee_payer(Origin::signed(ALICE), CAROL, false));
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_err());
+		assert_eq!(PalletBalances::free_balance(ALICE), alice_balance - 1046);
+
+		assert_ok!(Accounts::set_multisig_fee_payer(Origin::signed(CAROL), None));
+		assert_eq!(Accounts::multisig_fee_payer(&CAROL), None);
+	});
+}
+
+#[test]
+fn fee_existence_requirement_follows_allow_death_calls() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let call_index = CALL.using_encoded(Accounts::call_index_of).unwrap();
+
+		// keep alive by default
+		assert_eq!(
+			Accounts::fee_existence_requirement(CALL),
+			ExistenceRequirement::KeepAlive
+		);
+
+		assert_noop!(
+			Accounts::set_allow_death_call(Origin::signed(ALICE), call_index, true),
+			BadOrigin
+		);
+		assert_ok!(Accounts::set_allow_death_call(Origin::signed(0), call_index, true));
+		assert_eq!(Accounts::allow_death_calls(call_index), true);
+		let allow_death_call_updated_event = TestEvent::accounts(RawEvent::AllowDeathCallUpdated(call_index, true));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == allow_death_call_updated_event));
+
+		// only the allowed call can kill the account
+		assert_eq!(
+			Accounts::fee_existence_requirement(CALL),
+			ExistenceRequirement::AllowDeath
+		);
+		assert_eq!(
+			Accounts::fee_existence_requirement(CALL2),
+			ExistenceRequirement::KeepAlive
+		);
+
+		assert_ok!(Accounts::set_allow_death_call(Origin::signed(0), call_index, false));
+		assert_eq!(Accounts::allow_death_calls(call_index), false);
+		assert_eq!(
+			Accounts::fee_existence_requirement(CALL),
+			ExistenceRequirement::KeepAlive
+		);
+	});
+}
+
+#[test]
+fn fee_swaps_are_limited_per_block() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_max_fee_swaps_per_block(2);
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 50000, 5000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_eq!(Accounts::is_explicit(&BOB), true);
+
+		for _ in 0..2 {
+			assert!(ChargeTransactionPayment::<Runtime>::from(0)
+				.pre_dispatch(&BOB, CALL2, &INFO, 23)
+				.is_ok());
+		}
+		assert_eq!(Accounts::swaps_this_block(), 2);
+
+		// the non-native payer is deferred, the native payer is not affected
+		let ausd_balance = <Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_err());
+		assert_eq!(<Currencies as MultiCurrency<_>>::free_balance(AUSD, &BOB), ausd_balance);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Accounts::swaps_this_block(), 2);
+
+		// reset in the next block
+		Accounts::on_finalize(1);
+		assert_eq!(Accounts::swaps_this_block(), 0);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Accounts::swaps_this_block(), 1);
+	});
+}
+
+#[test]
+fn all_balances_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 10));
+
+		assert_eq!(Accounts::all_balances(&BOB, false), vec![(ACA, 900, 100), (BTC, 10, 0)]);
+		assert_eq!(
+			Accounts::all_balances(&BOB, true),
+			vec![(ACA, 900, 100), (AUSD, 0, 0), (BTC, 10, 0)]
+		);
+		assert_eq!(Accounts::all_balances(&CAROL, false), vec![]);
+	});
+}
+
+#[test]
+fn would_reap_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		// not opened account can not be reaped
+		assert_eq!(Accounts::would_reap(&BOB, ACA, 0), false);
+
+		// the reserved open account deposit keeps the account alive
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &BOB, 200));
+		assert_eq!(Accounts::would_reap(&BOB, ACA, 100), false);
+
+		// the account kept alive without deposit is reaped once native is gone
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(ACA, &ALICE, &CAROL, 90));
+		assert_eq!(Accounts::open_deposits(&CAROL), Some(0));
+		assert_eq!(Accounts::would_reap(&CAROL, ACA, 90), true);
+		assert_eq!(Accounts::would_reap(&CAROL, ACA, 50), false);
+		assert_eq!(Accounts::would_reap(&CAROL, ACA, 91), false);
+
+		// the remaining non-native keeps the account alive
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &CAROL, 10));
+		assert_eq!(Accounts::would_reap(&CAROL, ACA, 90), false);
+		assert_eq!(Accounts::would_reap(&CAROL, AUSD, 10), false);
+
+		assert_ok!(<Currencies as MultiCurrency<_>>::withdraw(ACA, &CAROL, 90));
+		assert_eq!(Accounts::would_reap(&CAROL, AUSD, 10), true);
+		assert_eq!(Accounts::would_reap(&CAROL, AUSD, 5), false);
+	});
+}
+
+#[test]
+fn tip_derived_from_priority_multiplier() {
+	ExtBuilder::default().build().execute_with(|| {
+		let fee = 23 * 2 + 1000; // len * byte + weight
+
+		// the tip is the base fee for 2x priority
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from_priority_multiplier(Ratio::saturating_from_integer(2))
+				.validate(&ALICE, CALL, &INFO, 23)
+				.unwrap()
+				.priority,
+			fee * 2 * 1024 / 1000
+		);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - fee * 2);
+
+		// no tip if the multiplier is not above one
+		assert_eq!(
+			ChargeTransactionPayment::<Runtime>::from_priority_multiplier(Ratio::saturating_from_rational(1, 2))
+				.validate(&ALICE, CALL, &INFO, 23)
+				.unwrap()
+				.priority,
+			fee * 1024 / 1000
+		);
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - fee * 3);
+	});
+}
+
+#[test]
+fn quote_fee_swap_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(DEXModule::add_liquidity(Origin::signed(ALICE), ACA, AUSD, 10000, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(AUSD, &ALICE, &BOB, 1000));
+		assert_ok!(<Currencies as MultiCurrency<_>>::transfer(BTC, &ALICE, &BOB, 100));
+		assert_eq!(PalletBalances::free_balance(BOB), 0);
+
+		// no liquidity for BTC
+		assert_eq!(Accounts::quote_fee_swap(&BOB, BTC, 1046), None);
+		// the supply amount exceeds `MaxFeeSwapAmount`
+		assert_eq!(Accounts::quote_fee_swap(&BOB, AUSD, 5000), None);
+
+		// the quote is what the fee swap consumes, and it mutates nothing
+		let pool = DEXModule::get_liquidity_pool(ACA, AUSD);
+		let quote = Accounts::quote_fee_swap(&BOB, AUSD, 1046).unwrap();
+		assert_eq!(DEXModule::get_liquidity_pool(ACA, AUSD), pool);
+		let ausd_balance = Currencies::free_balance(AUSD, &BOB);
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&BOB, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(AUSD, &BOB), ausd_balance - quote);
+	});
+}
+
+#[test]
+fn charges_multiplied_fee_for_call() {
+	ExtBuilder::default().build().execute_with(|| {
+		System::set_block_number(1);
+		let call_index = CALL2.using_encoded(Accounts::call_index_of).unwrap();
+		let multiplier = Ratio::saturating_from_integer(3);
+
+		assert_noop!(
+			Accounts::set_call_fee_multiplier(Origin::signed(ALICE), call_index, Some(multiplier)),
+			BadOrigin
+		);
+		assert_ok!(Accounts::set_call_fee_multiplier(
+			Origin::signed(0),
+			call_index,
+			Some(multiplier)
+		));
+		assert_eq!(Accounts::call_fee_multiplier(call_index), Some(multiplier));
+		let call_fee_multiplier_updated_event =
+			TestEvent::accounts(RawEvent::CallFeeMultiplierUpdated(call_index, Some(multiplier)));
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == call_fee_multiplier_updated_event));
+
+		// the base fee is multiplied, but not the tip
+		assert!(ChargeTransactionPayment::<Runtime>::from(10)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 1046 * 3 - 10);
+
+		// the call not listed pays the base fee
+		let fee = 23 * 2 + 1000; // len * byte + weight
+		assert!(ChargeTransactionPayment::<Runtime>::from(0)
+			.pre_dispatch(&ALICE, CALL, &INFO, 23)
+			.is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 1046 * 3 - 10 - fee);
+
+		assert_ok!(Accounts::set_call_fee_multiplier(Origin::signed(0), call_index, None));
+		assert_eq!(Accounts::call_fee_multiplier(call_index), None);
+	});
+}
+
+#[test]
+fn refund_multiplied_fee_according_to_actual_when_post_dispatch() {
+	ExtBuilder::default().build().execute_with(|| {
+		let call_index = CALL2.using_encoded(Accounts::call_index_of).unwrap();
+		assert_ok!(Accounts::set_call_fee_multiplier(
+			Origin::signed(0),
+			call_index,
+			Some(Ratio::saturating_from_integer(3))
+		));
+
+		let pre = ChargeTransactionPayment::<Runtime>::from(10)
+			.pre_dispatch(&ALICE, CALL2, &INFO, 23)
+			.unwrap();
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 1046 * 3 - 10);
+
+		// the actual base fee is multiplied as well, only the surplus is refunded
+		assert!(ChargeTransactionPayment::<Runtime>::post_dispatch(pre, &INFO, &POST_INFO, 23, &Ok(())).is_ok());
+		assert_eq!(Currencies::free_balance(ACA, &ALICE), 100000 - 846 * 3 - 10);
 	});
 }
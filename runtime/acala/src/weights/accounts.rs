@@ -1,4 +1,11 @@
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 2.0.0
+//! Weights for module_accounts
+//!
+//! `close_account` WAS GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION
+//! 2.0.0. THE OTHER WEIGHTS ARE ESTIMATED FROM THE STORAGE ACCESSES OF EACH
+//! CALL, NOT GENERATED. REGENERATE THIS FILE FROM THE BENCHMARKS IN
+//! `runtime/mandala/src/benchmarking/accounts.rs` WITH:
+//! `acala benchmark --chain=dev --steps=50 --repeat=20 --pallet=module_accounts
+//! --extrinsic="*" --execution=wasm --wasm-execution=compiled --heap-pages=4096`
 
 #![allow(unused_parens)]
 #![allow(unused_imports)]
@@ -17,4 +24,135 @@ impl<T: frame_system::Trait> module_accounts::WeightInfo for WeightInfo<T> {
 			.saturating_add(DbWeight::get().writes(1 as Weight))
 			.saturating_add(DbWeight::get().writes((2 as Weight).saturating_mul(c as Weight)))
 	}
+	// mutate the consolidation owner
+	fn set_consolidation_owner() -> Weight {
+		(21_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// read and write the pending close list, reserve the deposit
+	fn request_close() -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	// sr25519 verification dominates, reads nonce, genesis hash, last change and
+	// fee currency, writes nonce, last change and fee currency
+	fn set_fee_currency_signed() -> Weight {
+		(86_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	// `close_account` plus a two hop swap per currency, 2 pools and 3 balances
+	// read and written per hop
+	fn close_account_to_currency(c: u32) -> Weight {
+		(343_000_000 as Weight)
+			.saturating_add((118_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().reads((8 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes(15 as Weight))
+			.saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(c as Weight)))
+	}
+	// read and write the last change and the fee currency
+	fn set_fee_currency() -> Weight {
+		(23_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// two hop swap: 2 pools, the balances of the account and of DEX
+	fn swap_fee() -> Weight {
+		(76_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	// custom path, slippage limits, balance and 3 pools of the candidate paths
+	fn cache_fee_path() -> Weight {
+		(58_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// insert or remove one approval
+	fn approve_fee_payer() -> Weight {
+		(22_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// check the approval and write the payer
+	fn set_multisig_fee_payer() -> Weight {
+		(24_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// insert or remove one flag
+	fn set_auto_swap() -> Weight {
+		(18_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// mutate the custom path of at most `MaxFeeSwapHops` hops
+	fn set_fee_swap_path() -> Weight {
+		(21_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// swap the deposit with DEX and reserve it: pools, balances, the account and
+	// the open deposit, and write the referrer
+	fn try_open_account() -> Weight {
+		(95_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+	}
+	// put one value
+	fn set_new_account_deposit() -> Weight {
+		(20_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// put one value
+	fn set_max_slippage() -> Weight {
+		(20_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// mutate the slippage of the currency
+	fn set_currency_slippage() -> Weight {
+		(21_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// insert or remove one call
+	fn set_fee_free_call() -> Weight {
+		(20_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// insert or remove one call
+	fn set_fee_relief_call() -> Weight {
+		(20_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// insert or remove one call
+	fn set_allow_death_call() -> Weight {
+		(20_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// insert or remove one multiplier
+	fn set_call_fee_multiplier() -> Weight {
+		(20_000_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	// read and write the balances of the account and the recipient per currency
+	fn sweep_currencies(c: u32) -> Weight {
+		(40_000_000 as Weight)
+			.saturating_add((60_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads((2 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes((2 as Weight).saturating_mul(c as Weight)))
+	}
+	// native balance, locks and preference, then a swap tried per currency
+	fn withdraw_fee(c: u32) -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add((45_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().reads((4 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// the account and its locks, then at most 128 checks of the binary search
+	fn usable_native_balance() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+	}
+	// remove the native entry and one entry per currency
+	fn reset_fees_collected(c: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((5_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(c as Weight)))
+	}
 }
@@ -1,47 +1,362 @@
-use crate::{AccountId, AllNonNativeCurrencyIds, Balance, GetNativeCurrencyId, Runtime, DOLLARS};
+use crate::{
+	AccountId, Accounts, AllNonNativeCurrencyIds, Balance, Balances, Currencies, CurrencyId, Dex, GetNativeCurrencyId,
+	GetStableCurrencyId, MaxFeeSwapHops, MaxPendingCloses, NewAccountDeposit, Ratio, Runtime, Signature, TokenSymbol,
+	DOLLARS,
+};
 
 use super::utils::set_balance;
 use frame_benchmarking::account;
+use frame_support::{
+	traits::{LockableCurrency, StoredMap, WithdrawReason, WithdrawReasons},
+	StorageMap,
+};
 use frame_system::RawOrigin;
+use module_accounts::FeesCollectedByCurrency;
 use orml_benchmarking::runtime_benchmarks;
+use orml_traits::{MultiCurrency, MultiReservableCurrency};
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{traits::IdentifyAccount, FixedPointNumber, MultiSigner};
 use sp_std::prelude::*;
 
 const SEED: u32 = 0;
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"acct");
 
 fn dollar(d: u32) -> Balance {
 	let d: Balance = d.into();
 	DOLLARS.saturating_mul(d)
 }
 
+fn inject_liquidity(
+	maker: AccountId,
+	currency_id_a: CurrencyId,
+	currency_id_b: CurrencyId,
+	amount_a: Balance,
+	amount_b: Balance,
+) -> Result<(), &'static str> {
+	set_balance(currency_id_a, &maker, amount_a);
+	set_balance(currency_id_b, &maker, amount_b);
+	Dex::add_liquidity(
+		RawOrigin::Signed(maker).into(),
+		currency_id_a,
+		currency_id_b,
+		amount_a,
+		amount_b,
+	)?;
+
+	Ok(())
+}
+
+// the pools between the stable currency and each of the other currencies,
+// so that every non-native currency is swapped to native through two hops
+fn inject_fee_liquidity() -> Result<(), &'static str> {
+	let stable_currency_id = GetStableCurrencyId::get();
+	let mut currency_ids = AllNonNativeCurrencyIds::get();
+	currency_ids.push(GetNativeCurrencyId::get());
+
+	for (i, currency_id) in currency_ids.into_iter().enumerate() {
+		if currency_id != stable_currency_id {
+			let maker: AccountId = account("maker", i as u32, SEED);
+			inject_liquidity(maker, stable_currency_id, currency_id, dollar(10000), dollar(10000))?;
+		}
+	}
+
+	Ok(())
+}
+
 runtime_benchmarks! {
 	{ Runtime, module_accounts }
 
 	_ {}
 
+	// `close_account`, worst case:
+	// the open account deposit is reserved in native currency, and `c` non-native
+	// currencies have balances to be transferred, up to all listed currencies
 	close_account {
-		let c in 0 .. AllNonNativeCurrencyIds::get().len().saturating_sub(1) as u32;
+		let c in 0 .. AllNonNativeCurrencyIds::get().len() as u32;
 		let currency_ids = AllNonNativeCurrencyIds::get();
 		let caller: AccountId = account("caller", 0, SEED);
+		let recipient: AccountId = account("recipient", 0, SEED);
 		let native_currency_id = GetNativeCurrencyId::get();
 		set_balance(native_currency_id, &caller, dollar(1000));
+		set_balance(native_currency_id, &recipient, dollar(1000));
+		assert_eq!(
+			<Currencies as MultiReservableCurrency<_>>::reserved_balance(native_currency_id, &caller),
+			NewAccountDeposit::get()
+		);
 
 		for i in 0 .. c {
 			let currency_id = currency_ids[i as usize];
 			set_balance(currency_id, &caller, dollar(1000));
 		}
+	}: _(RawOrigin::Signed(caller.clone()), Some(recipient))
+	verify {
+		assert_eq!(Accounts::is_explicit(&caller), false);
+	}
+
+	// `withdraw_fee`, per currency tried:
+	// native currency is not enough to pay fee, and the swap of each of the `c`
+	// non-native currencies held is tried without liquidity
+	withdraw_fee {
+		let c in 1 .. AllNonNativeCurrencyIds::get().len() as u32;
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		let caller: AccountId = account("caller", 0, SEED);
+		set_balance(GetNativeCurrencyId::get(), &caller, NewAccountDeposit::get());
+
+		for i in 0 .. c {
+			let currency_id = currency_ids[i as usize];
+			set_balance(currency_id, &caller, dollar(1000));
+		}
+	}: {
+		let _ = Accounts::ensure_can_charge_fee(&caller, dollar(1), WithdrawReason::TransactionPayment.into());
+	}
+
+	// `usable_native_balance`, worst case:
+	// the native currency is partially locked, so the binary search runs the
+	// most steps
+	usable_native_balance {
+		let caller: AccountId = account("caller", 0, SEED);
+		set_balance(GetNativeCurrencyId::get(), &caller, dollar(1000));
+		Balances::set_lock(*b"accounts", &caller, dollar(500) + 1, WithdrawReasons::all());
+	}: {
+		let _ = Accounts::usable_native_balance_for(&caller, WithdrawReason::TransactionPayment.into());
+	}
+
+	// `withdraw_fee` with empty currencies, to compare with `withdraw_fee`:
+	// only the non-native currency at index `c` is held, the `c` currencies
+	// before it have no balance and are skipped without any DEX call
+	withdraw_fee_skip_empty_currencies {
+		let c in 0 .. AllNonNativeCurrencyIds::get().len() as u32 - 1;
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		let caller: AccountId = account("caller", 0, SEED);
+		set_balance(GetNativeCurrencyId::get(), &caller, NewAccountDeposit::get());
+		set_balance(currency_ids[c as usize], &caller, dollar(1000));
+	}: {
+		let _ = Accounts::ensure_can_charge_fee(&caller, dollar(1), WithdrawReason::TransactionPayment.into());
+	}
+
+	set_consolidation_owner {
+		let caller: AccountId = account("caller", 0, SEED);
+		let owner: AccountId = account("owner", 0, SEED);
+	}: _(RawOrigin::Signed(caller.clone()), Some(owner.clone()))
+	verify {
+		assert_eq!(Accounts::consolidation_owner(&caller), Some(owner));
+	}
+
+	// `request_close`, worst case:
+	// the pending close requests are full but one
+	request_close {
+		let caller: AccountId = account("caller", 0, SEED);
+		set_balance(GetNativeCurrencyId::get(), &caller, dollar(10));
+		for i in 0 .. MaxPendingCloses::get() - 1 {
+			let who: AccountId = account("pending", i, SEED);
+			set_balance(GetNativeCurrencyId::get(), &who, dollar(10));
+			Accounts::request_close(RawOrigin::Signed(who).into(), None)?;
+		}
 	}: _(RawOrigin::Signed(caller), None)
+	verify {
+		assert_eq!(Accounts::pending_closes().len() as u32, MaxPendingCloses::get());
+	}
+
+	// `set_fee_currency_signed`, worst case:
+	// the signature is verified over the payload with the genesis hash, and the
+	// fee currency has been set before
+	set_fee_currency_signed {
+		let caller: AccountId = account("caller", 0, SEED);
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		let public = sp_io::crypto::sr25519_generate(KEY_TYPE, None);
+		let who: AccountId = MultiSigner::from(public.clone()).into_account();
+		Accounts::set_fee_currency(RawOrigin::Signed(who.clone()).into(), Some(currency_ids[0]))?;
+
+		let payload = Accounts::fee_currency_payload(&who, Some(currency_ids[1]), 0);
+		let signature: Signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &public, &payload)
+			.ok_or("signing failed")?
+			.into();
+	}: _(RawOrigin::Signed(caller), who.clone(), Some(currency_ids[1]), signature)
+	verify {
+		assert_eq!(Accounts::fee_currency(&who), Some(currency_ids[1]));
+	}
+
+	// `close_account_to_currency`, worst case:
+	// each of the `c` non-native currencies held is swapped to native currency
+	// through two hops before the account is closed
+	close_account_to_currency {
+		let c in 0 .. AllNonNativeCurrencyIds::get().len() as u32;
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		let caller: AccountId = account("caller", 0, SEED);
+		let recipient: AccountId = account("recipient", 0, SEED);
+		let native_currency_id = GetNativeCurrencyId::get();
+		set_balance(native_currency_id, &caller, dollar(10));
+		set_balance(native_currency_id, &recipient, dollar(10));
+		inject_fee_liquidity()?;
+
+		for i in 0 .. c {
+			let currency_id = currency_ids[i as usize];
+			set_balance(currency_id, &caller, dollar(100));
+		}
+	}: _(RawOrigin::Signed(caller.clone()), native_currency_id, Some(recipient))
+	verify {
+		assert_eq!(Accounts::is_explicit(&caller), false);
+	}
+
+	set_fee_currency {
+		let caller: AccountId = account("caller", 0, SEED);
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		Accounts::set_fee_currency(RawOrigin::Signed(caller.clone()).into(), Some(currency_ids[0]))?;
+	}: _(RawOrigin::Signed(caller.clone()), Some(currency_ids[1]))
+	verify {
+		assert_eq!(Accounts::fee_currency(&caller), Some(currency_ids[1]));
+	}
+
+	// the extra weight of `withdraw_fee` when the fee is paid by swapping:
+	// native currency is not enough, and the fee is swapped from a non-native
+	// currency through two hops
+	swap_fee {
+		let caller: AccountId = account("caller", 0, SEED);
+		let currency_id = CurrencyId::Token(TokenSymbol::DOT);
+		set_balance(currency_id, &caller, dollar(100));
+		inject_fee_liquidity()?;
+	}: {
+		Accounts::ensure_can_charge_fee(&caller, dollar(1), WithdrawReason::TransactionPayment.into())?;
+	}
+	verify {
+		assert!(Currencies::free_balance(currency_id, &caller) < dollar(100));
+	}
+
+	// `cache_fee_path`:
+	// the custom path and the direct path are both tried with DEX
+	cache_fee_path {
+		let caller: AccountId = account("caller", 0, SEED);
+		let currency_id = CurrencyId::Token(TokenSymbol::DOT);
+		set_balance(currency_id, &caller, dollar(100));
+		inject_fee_liquidity()?;
+	}: _(RawOrigin::Signed(caller.clone()), currency_id)
+	verify {
+		assert!(Accounts::cached_fee_path(&caller, currency_id).is_some());
+	}
+
+	approve_fee_payer {
+		let caller: AccountId = account("caller", 0, SEED);
+		let who: AccountId = account("who", 0, SEED);
+	}: _(RawOrigin::Signed(caller.clone()), who.clone(), true)
+	verify {
+		assert!(Accounts::fee_payer_approvals(&caller, &who));
+	}
+
+	set_multisig_fee_payer {
+		let multisig: AccountId = account("multisig", 0, SEED);
+		let payer: AccountId = account("payer", 0, SEED);
+		Accounts::approve_fee_payer(RawOrigin::Signed(payer.clone()).into(), multisig.clone(), true)?;
+	}: _(RawOrigin::Signed(multisig.clone()), Some(payer.clone()))
+	verify {
+		assert_eq!(Accounts::multisig_fee_payer(&multisig), Some(payer));
+	}
+
+	set_auto_swap {
+		let caller: AccountId = account("caller", 0, SEED);
+	}: _(RawOrigin::Signed(caller.clone()), false)
+	verify {
+		assert!(Accounts::no_auto_swap(&caller));
+	}
+
+	// `set_fee_swap_path`, worst case:
+	// the path has `MaxFeeSwapHops` hops
+	set_fee_swap_path {
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		let currency_id = currency_ids[0];
+		let mut path = currency_ids[.. MaxFeeSwapHops::get() as usize].to_vec();
+		path.push(GetNativeCurrencyId::get());
+	}: _(RawOrigin::Root, currency_id, Some(path.clone()))
+	verify {
+		assert_eq!(Accounts::custom_fee_swap_path(currency_id), Some(path));
+	}
+
+	// `try_open_account`, worst case:
+	// the account does not exist, and the open account deposit is swapped from
+	// the non-native currency it holds, with the referrer
+	try_open_account {
+		let caller: AccountId = account("caller", 0, SEED);
+		let referrer: AccountId = account("referrer", 0, SEED);
+		let funder: AccountId = account("funder", 0, SEED);
+		let currency_id = GetStableCurrencyId::get();
+		set_balance(currency_id, &funder, dollar(100));
+		<Currencies as MultiCurrency<_>>::transfer(currency_id, &funder, &caller, dollar(100))?;
+		assert_eq!(Accounts::is_explicit(&caller), false);
+		inject_fee_liquidity()?;
+	}: _(RawOrigin::Signed(caller.clone()), Some(referrer.clone()))
+	verify {
+		assert_eq!(Accounts::is_explicit(&caller), true);
+		assert_eq!(Accounts::referrer(&caller), Some(referrer));
+	}
+
+	set_new_account_deposit {
+	}: _(RawOrigin::Root, dollar(1))
+	verify {
+		assert_eq!(Accounts::new_account_deposit(), dollar(1));
+	}
+
+	set_max_slippage {
+	}: _(RawOrigin::Root, Ratio::saturating_from_rational(1, 100))
+
+	set_currency_slippage {
+		let currency_id = AllNonNativeCurrencyIds::get()[0];
+	}: _(RawOrigin::Root, currency_id, Some(Ratio::saturating_from_rational(1, 100)))
+
+	set_fee_free_call {
+	}: _(RawOrigin::Root, (0, 0), true)
+
+	set_fee_relief_call {
+	}: _(RawOrigin::Root, (0, 0), true)
+
+	set_allow_death_call {
+	}: _(RawOrigin::Root, (0, 0), true)
+
+	set_call_fee_multiplier {
+	}: _(RawOrigin::Root, (0, 0), Some(Ratio::saturating_from_rational(2, 1)))
+
+	// `sweep_currencies`, per currency:
+	// the free balance of each of the `c` non-native currencies is transferred
+	sweep_currencies {
+		let c in 0 .. AllNonNativeCurrencyIds::get().len() as u32;
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		let caller: AccountId = account("caller", 0, SEED);
+		let recipient: AccountId = account("recipient", 0, SEED);
+		set_balance(GetNativeCurrencyId::get(), &recipient, dollar(10));
+
+		for i in 0 .. c {
+			let currency_id = currency_ids[i as usize];
+			set_balance(currency_id, &caller, dollar(100));
+		}
+	}: _(RawOrigin::Signed(caller), currency_ids[.. c as usize].to_vec(), recipient)
+
+	// `reset_fees_collected`, per currency:
+	// the fees are collected in native currency and `c` non-native currencies
+	reset_fees_collected {
+		let c in 0 .. AllNonNativeCurrencyIds::get().len() as u32;
+		let currency_ids = AllNonNativeCurrencyIds::get();
+		FeesCollectedByCurrency::insert(GetNativeCurrencyId::get(), dollar(1));
+
+		for i in 0 .. c {
+			FeesCollectedByCurrency::insert(currency_ids[i as usize], dollar(1));
+		}
+	}: _(RawOrigin::Root)
+	verify {
+		assert_eq!(Accounts::fees_collected(GetNativeCurrencyId::get()), 0);
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use frame_support::assert_ok;
+	use sp_core::{testing::KeyStore, traits::KeystoreExt};
 
 	fn new_test_ext() -> sp_io::TestExternalities {
-		frame_system::GenesisConfig::default()
+		let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::default()
 			.build_storage::<Runtime>()
 			.unwrap()
-			.into()
+			.into();
+		ext.register_extension(KeystoreExt(KeyStore::new()));
+		ext
 	}
 
 	#[test]
@@ -50,4 +365,172 @@ mod tests {
 			assert_ok!(test_benchmark_close_account());
 		});
 	}
+
+	#[test]
+	fn test_withdraw_fee() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_withdraw_fee());
+		});
+	}
+
+	#[test]
+	fn test_usable_native_balance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_usable_native_balance());
+		});
+	}
+
+	#[test]
+	fn test_withdraw_fee_skip_empty_currencies() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_withdraw_fee_skip_empty_currencies());
+		});
+	}
+
+	#[test]
+	fn test_set_consolidation_owner() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_consolidation_owner());
+		});
+	}
+
+	#[test]
+	fn test_request_close() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_request_close());
+		});
+	}
+
+	#[test]
+	fn test_set_fee_currency_signed() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_fee_currency_signed());
+		});
+	}
+
+	#[test]
+	fn test_close_account_to_currency() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_close_account_to_currency());
+		});
+	}
+
+	#[test]
+	fn test_set_fee_currency() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_fee_currency());
+		});
+	}
+
+	#[test]
+	fn test_swap_fee() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_swap_fee());
+		});
+	}
+
+	#[test]
+	fn test_cache_fee_path() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_cache_fee_path());
+		});
+	}
+
+	#[test]
+	fn test_approve_fee_payer() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_approve_fee_payer());
+		});
+	}
+
+	#[test]
+	fn test_set_multisig_fee_payer() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_multisig_fee_payer());
+		});
+	}
+
+	#[test]
+	fn test_set_auto_swap() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_auto_swap());
+		});
+	}
+
+	#[test]
+	fn test_set_fee_swap_path() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_fee_swap_path());
+		});
+	}
+
+	#[test]
+	fn test_try_open_account() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_try_open_account());
+		});
+	}
+
+	#[test]
+	fn test_set_new_account_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_new_account_deposit());
+		});
+	}
+
+	#[test]
+	fn test_set_max_slippage() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_max_slippage());
+		});
+	}
+
+	#[test]
+	fn test_set_currency_slippage() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_currency_slippage());
+		});
+	}
+
+	#[test]
+	fn test_set_fee_free_call() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_fee_free_call());
+		});
+	}
+
+	#[test]
+	fn test_set_fee_relief_call() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_fee_relief_call());
+		});
+	}
+
+	#[test]
+	fn test_set_allow_death_call() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_allow_death_call());
+		});
+	}
+
+	#[test]
+	fn test_set_call_fee_multiplier() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_call_fee_multiplier());
+		});
+	}
+
+	#[test]
+	fn test_sweep_currencies() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_sweep_currencies());
+		});
+	}
+
+	#[test]
+	fn test_reset_fees_collected() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_reset_fees_collected());
+		});
+	}
 }
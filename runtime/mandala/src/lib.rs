@@ -88,10 +88,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("mandala"),
 	impl_name: create_runtime_str!("mandala"),
 	authoring_version: 1,
-	spec_version: 602,
+	spec_version: 603,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// The version infromation used to identify this runtime when compiled
@@ -125,6 +125,7 @@ parameter_types! {
 	pub const DSWFModuleId: ModuleId = ModuleId(*b"aca/dswf");
 	pub const ElectionsPhragmenModuleId: LockIdentifier = *b"aca/phre";
 	pub const NftModuleId: ModuleId = ModuleId(*b"aca/aNFT");
+	pub const FeeReliefFundModuleId: ModuleId = ModuleId(*b"aca/frlf");
 }
 
 pub fn get_all_module_accounts() -> Vec<AccountId> {
@@ -138,6 +139,7 @@ pub fn get_all_module_accounts() -> Vec<AccountId> {
 		HomaTreasuryModuleId::get().into_account(),
 		IncentivesModuleId::get().into_account(),
 		DSWFModuleId::get().into_account(),
+		FeeReliefFundModuleId::get().into_account(),
 		ZeroAccountId::get(),
 	]
 }
@@ -954,8 +956,8 @@ where
 			frame_system::CheckGenesis::<Runtime>::new(),
 			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
 			frame_system::CheckNonce::<Runtime>::from(nonce),
-			frame_system::CheckWeight::<Runtime>::new(),
 			module_accounts::ChargeTransactionPayment::<Runtime>::from(tip),
+			frame_system::CheckWeight::<Runtime>::new(),
 		);
 		let raw_payload = SignedPayload::new(call, extra)
 			.map_err(|e| {
@@ -1067,19 +1069,123 @@ parameter_types! {
 	// All currency types except for native currency, Sort by fee charge order
 	pub AllNonNativeCurrencyIds: Vec<CurrencyId> = vec![CurrencyId::Token(TokenSymbol::AUSD), CurrencyId::Token(TokenSymbol::LDOT), CurrencyId::Token(TokenSymbol::DOT), CurrencyId::Token(TokenSymbol::XBTC), CurrencyId::Token(TokenSymbol::RENBTC)];
 	pub const NewAccountDeposit: Balance = 100 * MILLICENTS;
+	pub const BlockAuthorShare: Perbill = Perbill::from_percent(0);
+	pub const TipToBlockAuthor: bool = false;
+	pub const MaxConsolidateSources: u32 = 10;
+	pub const PendingCloseWindow: BlockNumber = 7 * DAYS;
+	pub const MaxPendingCloses: u32 = 50;
+	pub const CloseRequestDeposit: Balance = DOLLARS;
+	pub const MaxClosesPerBlock: u32 = 5;
+	pub FeeReserveFloors: Vec<(CurrencyId, Balance)> = vec![];
+	pub const AccountsEventVerbosity: module_accounts::EventLevel = module_accounts::EventLevel::Verbose;
+	pub const FeePreferenceCooldown: BlockNumber = 0;
+	pub const ZeroFeePriority: TransactionPriority = 1;
+	pub const FeePathCacheLifetime: BlockNumber = HOURS;
+	pub const ReferralShare: Perbill = Perbill::from_percent(0);
+	pub const MaxReferralPayoutPerEra: Balance = 0;
+	pub const MaxRecentlyKilled: u32 = 100;
+	pub const MaxFeeSwapAmount: Balance = Balance::max_value();
+	pub const MaxCloseBatch: u32 = 50;
+	pub const DustThreshold: Balance = 100 * MILLICENTS;
+	pub const OpenAttemptCooldown: BlockNumber = 10 * MINUTES;
+	pub const FeeReliefEnabled: bool = false;
+	pub const MaxFeeReliefDebt: Balance = 10 * CENTS;
+	pub ExcludedFromAutoOpen: Vec<AccountId> = get_all_module_accounts();
+	pub const FreeQuotaPerEra: u32 = 0;
+	pub MinSwapAmounts: Vec<(CurrencyId, Balance)> = vec![];
+	pub const MaxFeeSwapHops: u32 = 3;
+	pub AccountsStableCurrencyIds: Vec<CurrencyId> = vec![CurrencyId::Token(TokenSymbol::AUSD)];
+	pub const MaxFeeSwapsPerBlock: u32 = 100;
+	pub DustBurnRatio: Ratio = Ratio::saturating_from_rational(0, 100);
+	pub const NonNativeDeposit: Option<Balance> = None;
+	pub FeeLockLimits: Vec<(LockIdentifier, Balance)> = vec![];
+}
+
+pub struct StakingPoolEra;
+impl Get<EraIndex> for StakingPoolEra {
+	fn get() -> EraIndex {
+		StakingPool::current_era()
+	}
+}
+
+pub struct MultisigApprovalOf;
+impl module_accounts::MultisigApproval<AccountId, Call> for MultisigApprovalOf {
+	fn multisig_of(who: &AccountId, call: &Call) -> Option<AccountId> {
+		let (threshold, other_signatories) = match call {
+			Call::Multisig(pallet_multisig::Call::as_multi(threshold, other_signatories, ..))
+			| Call::Multisig(pallet_multisig::Call::approve_as_multi(threshold, other_signatories, ..)) => {
+				(*threshold, other_signatories)
+			}
+			Call::Multisig(pallet_multisig::Call::as_multi_threshold_1(other_signatories, ..)) => (1, other_signatories),
+			_ => return None,
+		};
+		let mut signatories = other_signatories.clone();
+		signatories.push(who.clone());
+		signatories.sort();
+		Some(Multisig::multi_account_id(&signatories, threshold))
+	}
 }
 
 impl module_accounts::Trait for Runtime {
+	type Event = Event;
 	type AllNonNativeCurrencyIds = AllNonNativeCurrencyIds;
 	type NativeCurrencyId = GetNativeCurrencyId;
 	type StableCurrencyId = GetStableCurrencyId;
+	type StableCurrencyIds = AccountsStableCurrencyIds;
 	type Currency = Currencies;
 	type DEX = Dex;
 	type OnCreatedAccount = frame_system::CallOnCreatedAccount<Runtime>;
+	type OnAccountOpenedViaSwap = ();
 	type KillAccount = frame_system::CallKillAccount<Runtime>;
+	type OnKillAccount = ();
 	type NewAccountDeposit = NewAccountDeposit;
+	type OpenDepositDiscount = ();
 	type TreasuryModuleId = AcalaTreasuryModuleId;
 	type MaxSlippageSwapWithDEX = MaxSlippageSwapWithDEX;
+	type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Babe>;
+	type BlockAuthorShare = BlockAuthorShare;
+	type TipToBlockAuthor = TipToBlockAuthor;
+	type MaxConsolidateSources = MaxConsolidateSources;
+	type LostRefunds = AcalaTreasury;
+	type PendingCloseWindow = PendingCloseWindow;
+	type MaxPendingCloses = MaxPendingCloses;
+	type CloseRequestDeposit = CloseRequestDeposit;
+	type MaxClosesPerBlock = MaxClosesPerBlock;
+	type FeeReserveFloors = FeeReserveFloors;
+	type MinSwapAmounts = MinSwapAmounts;
+	type EventVerbosity = AccountsEventVerbosity;
+	type Signature = Signature;
+	type Signer = <Signature as sp_runtime::traits::Verify>::Signer;
+	type FeePreferenceCooldown = FeePreferenceCooldown;
+	type TransferGuard = ();
+	type ZeroFeePriority = ZeroFeePriority;
+	type FeePathCacheLifetime = FeePathCacheLifetime;
+	type ReferralShare = ReferralShare;
+	type MaxReferralPayoutPerEra = MaxReferralPayoutPerEra;
+	type CallFeePolicy = ();
+	type MultisigApproval = MultisigApprovalOf;
+	type FeeLockAllowance = module_accounts::BalancesFeeLockAllowance<Runtime, FeeLockLimits>;
+	type FeeCurrencyOrder = module_accounts::FixedOrder;
+	type ExcludedFromAutoOpen = ExcludedFromAutoOpen;
+	type FreeQuotaPerEra = FreeQuotaPerEra;
+	type EraProvider = StakingPoolEra;
+	type MaxRecentlyKilled = MaxRecentlyKilled;
+	type MaxFeeSwapAmount = MaxFeeSwapAmount;
+	type MaxFeeSwapHops = MaxFeeSwapHops;
+	type MaxFeeSwapsPerBlock = MaxFeeSwapsPerBlock;
+	type UpdateOrigin = EnsureRootOrHalfGeneralCouncil;
+	type CloseOrigin = EnsureRootOrHalfGeneralCouncil;
+	type ForceCloseOrigin = EnsureRootOrThreeFourthsGeneralCouncil;
+	type MaxCloseBatch = MaxCloseBatch;
+	type DustThreshold = DustThreshold;
+	type DustBurnRatio = DustBurnRatio;
+	type DepositCurrencyId = GetNativeCurrencyId;
+	type NonNativeDeposit = NonNativeDeposit;
+	type AccountReferences = ();
+	type OpenAttemptCooldown = OpenAttemptCooldown;
+	type FeeReliefFund = FeeReliefFundModuleId;
+	type FeeReliefEnabled = FeeReliefEnabled;
+	type MaxFeeReliefDebt = MaxFeeReliefDebt;
 	type WeightInfo = weights::accounts::WeightInfo<Runtime>;
 }
 
@@ -1308,7 +1414,7 @@ construct_runtime!(
 		// Tokens & Related
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
 
-		Accounts: module_accounts::{Module, Call, Storage},
+		Accounts: module_accounts::{Module, Call, Storage, Event<T>, Config<T>},
 		Currencies: orml_currencies::{Module, Call, Event<T>},
 		Tokens: orml_tokens::{Module, Storage, Event<T>, Config<T>},
 		Vesting: orml_vesting::{Module, Storage, Call, Event<T>, Config<T>},
@@ -1414,8 +1520,9 @@ pub type SignedExtra = (
 	frame_system::CheckGenesis<Runtime>,
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
-	frame_system::CheckWeight<Runtime>,
+	// the weight of fee swaps registered by `ChargeTransactionPayment` is checked by `CheckWeight`
 	module_accounts::ChargeTransactionPayment<Runtime>,
+	frame_system::CheckWeight<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -1611,6 +1718,49 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl module_accounts_rpc_runtime_api::AccountsApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn recently_killed() -> Vec<(AccountId, BlockNumber)> {
+			Accounts::recently_killed()
+		}
+
+		fn usable_native_balance(who: AccountId) -> Balance {
+			Accounts::usable_native_balance(&who)
+		}
+
+		fn treasury_account() -> AccountId {
+			Accounts::treasury_account_id()
+		}
+
+		fn total_swept_dust() -> Balance {
+			Accounts::total_swept_dust()
+		}
+	}
+
+	impl module_accounts_rpc_runtime_api::AccountsFeeApi<Block, AccountId, Balance, Call, CurrencyId> for Runtime {
+		fn query_fee_in_currency(call: Call, len: u32, currency_id: CurrencyId) -> Option<Balance> {
+			let info = frame_support::weights::GetDispatchInfo::get_dispatch_info(&call);
+			Accounts::query_fee_in_currency(len, &info, currency_id)
+		}
+
+		fn fees_collected(currency_id: CurrencyId) -> Balance {
+			Accounts::fees_collected(currency_id)
+		}
+
+		fn payable_fee_currencies(who: AccountId, fee: Balance) -> Vec<CurrencyId> {
+			Accounts::payable_fee_currencies(&who, fee)
+		}
+	}
+
+	impl module_accounts_rpc_runtime_api::AccountsBalanceApi<Block, AccountId, Balance, CurrencyId> for Runtime {
+		fn all_balances(who: AccountId, include_zero: bool) -> Vec<(CurrencyId, Balance, Balance)> {
+			Accounts::all_balances(&who, include_zero)
+		}
+
+		fn would_reap(who: AccountId, currency_id: CurrencyId, amount: Balance) -> bool {
+			Accounts::would_reap(&who, currency_id, amount)
+		}
+	}
+
 	impl module_staking_pool_rpc_runtime_api::StakingPoolApi<
 		Block,
 		AccountId,